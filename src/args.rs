@@ -1,9 +1,14 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use cargo_metadata::DependencyKind;
 use clap::builder::PathBufValueParser;
 use clap::{Parser, Subcommand};
 
+use crate::fedora::PackagingProgress;
+use crate::graph::PkgSpec;
+use crate::style::Paint;
+
 #[derive(Parser, Default)]
 #[clap(bin_name = "cargo")]
 pub struct RpmStatus {
@@ -12,6 +17,7 @@ pub struct RpmStatus {
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Opts {
     #[command(name = "rpmstatus")]
     /// Display a tree visualization of a dependency graph
@@ -34,32 +40,57 @@ impl Default for Opts {
 /// Fedora rawhide and dependencies still missing for packaging.
 /// Dependencies already in the rawhide repo will show up green; those not
 /// in rawhide yet white.
-#[derive(Parser, Debug, Default)]
+#[derive(Parser, Debug, Default, Clone)]
 pub struct RpmArgs {
     #[arg(long = "package", short = 'p', value_name = "SPEC")]
     /// Package to be used as the root of the tree
     pub package: Option<String>,
+    #[arg(long = "workspace")]
+    /// Check all members of the workspace
+    pub workspace: bool,
     #[arg(long = "features", value_name = "FEATURES")]
     /// Space-separated list of features to activate
     pub features: Option<String>,
-    #[arg(long = "all-features")]
+    #[arg(long = "all-features", overrides_with = "no_all_features")]
     /// Activate all available features
     pub all_features: bool,
-    #[arg(long = "no-default-features")]
+    #[arg(long = "no-all-features")]
+    /// Undo `all-features = true` from the config
+    pub no_all_features: bool,
+    #[arg(long = "no-default-features", overrides_with = "default_features")]
     /// Do not activate the `default` feature
     pub no_default_features: bool,
+    #[arg(long = "default-features")]
+    /// Undo `no-default-features = true` from the config
+    pub default_features: bool,
     #[arg(long = "target", value_name = "TARGET")]
-    /// Set the target triple
+    /// Set the target triple instead of matching every Linux target Fedora builds for
     pub target: Option<String>,
-    #[arg(long = "all-targets")]
-    /// Return dependencies for all targets. By default only the host target is matched.
+    #[arg(long = "all-targets", overrides_with = "no_all_targets")]
+    /// Return dependencies for all targets and mark those not needed on Fedora's Linux targets
+    /// to be patched out. By default dependencies for other platforms are left out.
     pub all_targets: bool,
-    #[arg(long = "rpm-release", value_name = "RELEASE")]
-    /// Choose the target release (default rawhide, possible fedora versions (e.g. f39), or epel (e.g. epel9))
-    pub rpm_release: Option<String>,
-    #[arg(long = "no-dev-dependencies")]
+    #[arg(long = "no-all-targets")]
+    /// Undo `all-targets = true` from the config
+    pub no_all_targets: bool,
+    #[arg(long = "rpm-release", value_name = "RELEASE", value_delimiter = ',')]
+    /// Choose the target release (default rawhide, possible fedora versions (e.g. f39), or epel (e.g. epel9)), the summary compares every release given
+    pub rpm_release: Vec<String>,
+    #[arg(long = "no-dev-dependencies", overrides_with = "dev_dependencies")]
     /// Skip dev dependencies.
     pub no_dev_dependencies: bool,
+    #[arg(long = "dev-dependencies")]
+    /// Undo `no-dev-dependencies = true` from the config
+    pub dev_dependencies: bool,
+    #[arg(
+        long = "edges",
+        short = 'e',
+        value_name = "KINDS",
+        value_delimiter = ','
+    )]
+    /// Dependency kinds to follow: all, normal, build, dev, no-normal, no-build, no-dev,
+    /// no-proc-macro
+    pub edges: Vec<EdgeKind>,
     #[arg(
         long = "manifest-path",
         value_name = "PATH",
@@ -74,6 +105,28 @@ pub struct RpmArgs {
     )]
     /// Path to <package>.crate (from `rust2rpm -s`)
     pub crate_path: Option<PathBuf>,
+    #[arg(long = "upstream")]
+    /// Compare Fedora versions against the newest release in the local crates.io index
+    pub upstream: bool,
+    #[arg(long = "needs-bump")]
+    /// List only packaged crates lagging behind the newest upstream release (implies --upstream)
+    pub needs_bump: bool,
+    #[arg(long = "exclude", value_name = "SPEC")]
+    /// Remove a crate and the dependencies only it pulls in before checking the packaging status
+    pub exclude: Vec<PkgSpec>,
+    #[arg(long = "prune", value_name = "SPEC")]
+    /// Hide a crate and its dependencies from the tree
+    pub prune: Vec<PkgSpec>,
+    #[arg(long = "depth", value_name = "DEPTH")]
+    /// Maximum display depth of the dependency tree
+    pub depth: Option<usize>,
+    #[arg(long = "only", value_name = "STATUSES", value_delimiter = ',')]
+    /// Show only crates with these packaging statuses and the paths leading to them:
+    /// available, compatible, pending, spec-pending, outdated, missing, patch-out, blocker
+    pub only: Vec<PackagingProgress>,
+    #[arg(long = "flat")]
+    /// List every crate once with its status and the crates depending on it
+    pub flat: bool,
     #[arg(long = "invert", short = 'i')]
     /// Invert the tree direction
     pub invert: bool,
@@ -100,15 +153,60 @@ pub struct RpmArgs {
     )]
     /// Format string used for printing dependencies
     pub format: String,
+    #[arg(
+        long = "output",
+        short = 'o',
+        value_name = "FORMAT",
+        default_value = "tree"
+    )]
+    /// Output format: tree, json, dot, markdown, html, junit, cyclonedx
+    pub output: OutputFormat,
+    #[arg(long = "unpackaged")]
+    /// Only export crates needing packaging work and the crates they depend on (dot output)
+    pub unpackaged: bool,
+    #[arg(long = "assume-packaged", value_name = "SPEC[=NOTE]")]
+    /// Treat a crate as packaged, e.g. while it is under review or built in a side tag:
+    /// `foo@1.2=rhbz#2345678`
+    pub assume_packaged: Vec<AssumePackaged>,
+    #[arg(
+        long = "local-rpms",
+        value_name = "DIR",
+        value_parser(PathBufValueParser::new())
+    )]
+    /// Directory of locally built rpms, e.g. from `fedpkg mockbuild`, to count as packaged
+    pub local_rpms: Option<PathBuf>,
+    #[arg(
+        long = "spec-dir",
+        value_name = "DIR",
+        value_parser(PathBufValueParser::new())
+    )]
+    /// Directory of work-in-progress rust2rpm specs, reported as spec pending
+    pub spec_dir: Option<PathBuf>,
+    #[arg(long = "policy", value_name = "PATH")]
+    /// Packaging policy to check, exiting with 1 if it isn't met (default rpmstatus.toml if present)
+    pub policy: Option<PathBuf>,
     #[arg(long = "verbose", short = 'v', action = clap::ArgAction::Count)]
     /// Use verbose output (-vv very verbose/build.rs output)
     pub verbose: u8,
     #[arg(long = "quiet", short = 'q')]
-    /// No output printed to stdout other than the tree
+    /// No output printed to stdout other than the tree, skipping the summary footer
     pub quiet: bool,
     #[arg(long = "color", value_name = "WHEN")]
-    /// Coloring: auto, always, never
+    /// Coloring: auto, always, never (defaults to CARGO_TERM_COLOR, honours NO_COLOR)
     pub color: Option<String>,
+    #[arg(long = "icons", value_name = "THEME", default_value = "emoji")]
+    /// Status icons: emoji, ascii, none
+    pub icons: IconTheme,
+    #[arg(
+        long = "status-color",
+        value_name = "STATUS=COLOR",
+        value_delimiter = ','
+    )]
+    /// Color of crate names per status, e.g. missing=red,outdated=magenta (plain and dimmed are accepted too)
+    pub status_color: Vec<StatusColor>,
+    #[arg(long = "hyperlinks")]
+    /// Link crates to crates.io and Fedora versions to their package page, if printing to a terminal
+    pub hyperlinks: bool,
     #[arg(long = "frozen")]
     /// Require Cargo.lock and cache are up to date
     pub frozen: bool,
@@ -121,6 +219,140 @@ pub struct RpmArgs {
     #[arg(short = 'Z', value_name = "FLAG")]
     /// Unstable (nightly-only) flags to Cargo
     pub unstable_flags: Vec<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Show every dependency path from the root to a crate
+    Why(WhyArgs),
+    /// Compare two Cargo.lock files or two .crate archives
+    Diff(DiffArgs),
+    /// Print packaging statistics per release
+    Summary,
+    /// List the Fedora packages still requiring the packaged version of outdated crates
+    Impact,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct WhyArgs {
+    #[arg(value_name = "SPEC")]
+    /// Crate to explain, as `name` or `name@version`
+    pub package: PkgSpec,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct DiffArgs {
+    #[arg(value_name = "OLD", value_parser(PathBufValueParser::new()))]
+    /// Cargo.lock or .crate of the currently packaged version
+    pub old: PathBuf,
+    #[arg(value_name = "NEW", value_parser(PathBufValueParser::new()))]
+    /// Cargo.lock or .crate of the new version
+    pub new: PathBuf,
+}
+
+impl RpmArgs {
+    /// Combine `--edges` and `--no-dev-dependencies` into the kinds of dependencies to follow
+    pub fn edges(&self) -> Result<Edges, &'static str> {
+        let positive = self
+            .edges
+            .iter()
+            .any(|kind| matches!(kind, EdgeKind::Normal | EdgeKind::Build | EdgeKind::Dev));
+        let negative = self.edges.iter().any(|kind| {
+            matches!(
+                kind,
+                EdgeKind::NoNormal | EdgeKind::NoBuild | EdgeKind::NoDev
+            )
+        });
+        if positive && negative {
+            return Err("`--edges` can't mix dependency kinds with their `no-` variants");
+        }
+
+        let mut edges = Edges {
+            normal: !positive,
+            build: !positive,
+            dev: !positive,
+            proc_macro: true,
+        };
+        // like cargo, the `no-` variants apply after `all`, regardless of their order
+        for kind in &self.edges {
+            match kind {
+                EdgeKind::All => {
+                    edges.normal = true;
+                    edges.build = true;
+                    edges.dev = true;
+                }
+                EdgeKind::Normal => edges.normal = true,
+                EdgeKind::Build => edges.build = true,
+                EdgeKind::Dev => edges.dev = true,
+                _ => {}
+            }
+        }
+        for kind in &self.edges {
+            match kind {
+                EdgeKind::NoNormal => edges.normal = false,
+                EdgeKind::NoBuild => edges.build = false,
+                EdgeKind::NoDev => edges.dev = false,
+                EdgeKind::NoProcMacro => edges.proc_macro = false,
+                _ => {}
+            }
+        }
+        if self.no_dev_dependencies {
+            edges.dev = false;
+        }
+
+        Ok(edges)
+    }
+}
+
+/// The kinds of dependency edges to follow when building the graph
+#[derive(Debug, Clone, Copy)]
+pub struct Edges {
+    pub normal: bool,
+    pub build: bool,
+    pub dev: bool,
+    pub proc_macro: bool,
+}
+
+impl Edges {
+    pub fn includes(&self, kind: DependencyKind) -> bool {
+        match kind {
+            DependencyKind::Build => self.build,
+            DependencyKind::Development => self.dev,
+            _ => self.normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EdgeKind {
+    All,
+    Normal,
+    Build,
+    Dev,
+    NoNormal,
+    NoBuild,
+    NoDev,
+    NoProcMacro,
+}
+
+impl FromStr for EdgeKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<EdgeKind, &'static str> {
+        match s {
+            "all" => Ok(EdgeKind::All),
+            "normal" => Ok(EdgeKind::Normal),
+            "build" => Ok(EdgeKind::Build),
+            "dev" => Ok(EdgeKind::Dev),
+            "no-normal" => Ok(EdgeKind::NoNormal),
+            "no-build" => Ok(EdgeKind::NoBuild),
+            "no-dev" => Ok(EdgeKind::NoDev),
+            "no-proc-macro" => Ok(EdgeKind::NoProcMacro),
+            _ => Err("invalid edge kind"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -141,3 +373,113 @@ impl FromStr for Charset {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum IconTheme {
+    #[default]
+    Emoji,
+    Ascii,
+    None,
+}
+
+impl FromStr for IconTheme {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<IconTheme, &'static str> {
+        match s {
+            "emoji" => Ok(IconTheme::Emoji),
+            "ascii" => Ok(IconTheme::Ascii),
+            "none" => Ok(IconTheme::None),
+            _ => Err("invalid icon theme"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatusColor {
+    pub status: PackagingProgress,
+    pub paint: Paint,
+}
+
+impl FromStr for StatusColor {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<StatusColor, &'static str> {
+        let (status, paint) = s.split_once('=').ok_or("expected STATUS=COLOR")?;
+        Ok(StatusColor {
+            status: status.parse()?,
+            paint: paint.parse()?,
+        })
+    }
+}
+
+/// A crate assumed to be packaged soon, with an optional note like a review bug
+#[derive(Debug, Clone)]
+pub struct AssumePackaged {
+    pub spec: PkgSpec,
+    pub note: Option<String>,
+}
+
+impl FromStr for AssumePackaged {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<AssumePackaged, anyhow::Error> {
+        let (spec, note) = match s.split_once('=') {
+            Some((spec, note)) => (spec, Some(note.to_string())),
+            None => (s, None),
+        };
+        Ok(AssumePackaged {
+            spec: spec.parse()?,
+            note: note.filter(|note| !note.is_empty()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OutputFormat {
+    #[default]
+    Tree,
+    Json,
+    Dot,
+    Markdown,
+    Html,
+    Junit,
+    CycloneDx,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<OutputFormat, &'static str> {
+        match s {
+            "tree" => Ok(OutputFormat::Tree),
+            "json" => Ok(OutputFormat::Json),
+            "dot" => Ok(OutputFormat::Dot),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            "junit" => Ok(OutputFormat::Junit),
+            "cyclonedx" => Ok(OutputFormat::CycloneDx),
+            _ => Err("invalid output format"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::{EdgeKind, RpmArgs};
+
+    #[test]
+    fn edges_ignore_order() {
+        for kinds in [
+            [EdgeKind::NoDev, EdgeKind::All],
+            [EdgeKind::All, EdgeKind::NoDev],
+        ] {
+            let args = RpmArgs {
+                edges: kinds.to_vec(),
+                ..RpmArgs::default()
+            };
+            let edges = args.edges().unwrap();
+            assert!(edges.normal && edges.build && !edges.dev);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::args::RpmArgs;
+use crate::errors::*;
+use crate::metadata;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single value or a list of them, e.g. `release = "epel9"` or `release = ["rawhide", "f41"]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum List {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl List {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            List::One(value) => vec![value],
+            List::Many(values) => values,
+        }
+    }
+}
+
+/// Defaults for the command line flags, from the user config or `[package.metadata.rpmstatus]`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    release: Option<List>,
+    features: Option<List>,
+    all_features: Option<bool>,
+    no_default_features: Option<bool>,
+    no_dev_dependencies: Option<bool>,
+    all_targets: Option<bool>,
+    edges: Option<List>,
+    exclude: Option<List>,
+    prune: Option<List>,
+    output: Option<String>,
+    format: Option<String>,
+    icons: Option<String>,
+    status_color: Option<List>,
+    assume_packaged: Option<List>,
+    local_rpms: Option<PathBuf>,
+    spec_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Settings of `other` take precedence over ours
+    fn merge(self, other: Config) -> Config {
+        Config {
+            release: other.release.or(self.release),
+            features: other.features.or(self.features),
+            all_features: other.all_features.or(self.all_features),
+            no_default_features: other.no_default_features.or(self.no_default_features),
+            no_dev_dependencies: other.no_dev_dependencies.or(self.no_dev_dependencies),
+            all_targets: other.all_targets.or(self.all_targets),
+            edges: other.edges.or(self.edges),
+            exclude: other.exclude.or(self.exclude),
+            prune: other.prune.or(self.prune),
+            output: other.output.or(self.output),
+            format: other.format.or(self.format),
+            icons: other.icons.or(self.icons),
+            status_color: other.status_color.or(self.status_color),
+            assume_packaged: other.assume_packaged.or(self.assume_packaged),
+            local_rpms: other.local_rpms.or(self.local_rpms),
+            spec_dir: other.spec_dir.or(self.spec_dir),
+        }
+    }
+
+    /// Fill in every flag that wasn't given on the command line
+    pub fn apply(self, args: &mut RpmArgs, matches: &ArgMatches) -> Result<(), Error> {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        // boolean flags can be turned off again with their negation, e.g. `--no-all-features`
+        let unset_flag = |id: &str, negation: &str| unset(id) && unset(negation);
+
+        if unset("rpm_release")
+            && let Some(release) = self.release
+        {
+            args.rpm_release = release.into_vec();
+        }
+        if unset("features")
+            && let Some(features) = self.features
+        {
+            args.features = Some(features.into_vec().join(" "));
+        }
+        if unset_flag("all_features", "no_all_features")
+            && let Some(all_features) = self.all_features
+        {
+            args.all_features = all_features;
+        }
+        if unset_flag("no_default_features", "default_features")
+            && let Some(no_default_features) = self.no_default_features
+        {
+            args.no_default_features = no_default_features;
+        }
+        if unset_flag("no_dev_dependencies", "dev_dependencies")
+            && let Some(no_dev_dependencies) = self.no_dev_dependencies
+        {
+            args.no_dev_dependencies = no_dev_dependencies;
+        }
+        if unset_flag("all_targets", "no_all_targets")
+            && let Some(all_targets) = self.all_targets
+        {
+            args.all_targets = all_targets;
+        }
+        if unset("edges")
+            && let Some(edges) = self.edges
+        {
+            args.edges = parse_all(edges, "edges")?;
+        }
+        if unset("exclude")
+            && let Some(exclude) = self.exclude
+        {
+            args.exclude = parse_all(exclude, "exclude")?;
+        }
+        if unset("prune")
+            && let Some(prune) = self.prune
+        {
+            args.prune = parse_all(prune, "prune")?;
+        }
+        if unset("output")
+            && let Some(output) = self.output
+        {
+            args.output = parse(&output, "output")?;
+        }
+        if unset("format")
+            && let Some(format) = self.format
+        {
+            args.format = format;
+        }
+        if unset("icons")
+            && let Some(icons) = self.icons
+        {
+            args.icons = parse(&icons, "icons")?;
+        }
+        if unset("status_color")
+            && let Some(status_color) = self.status_color
+        {
+            args.status_color = parse_all(status_color, "status-color")?;
+        }
+        if unset("assume_packaged")
+            && let Some(assume_packaged) = self.assume_packaged
+        {
+            args.assume_packaged = parse_all(assume_packaged, "assume-packaged")?;
+        }
+        if unset("local_rpms")
+            && let Some(local_rpms) = self.local_rpms
+        {
+            args.local_rpms = Some(local_rpms);
+        }
+        if unset("spec_dir")
+            && let Some(spec_dir) = self.spec_dir
+        {
+            args.spec_dir = Some(spec_dir);
+        }
+
+        Ok(())
+    }
+}
+
+fn parse<T>(value: &str, key: &str) -> Result<T, Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|err| anyhow::anyhow!("invalid `{key}` value `{value}`: {err}"))
+}
+
+fn parse_all<T>(values: List, key: &str) -> Result<Vec<T>, Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    values
+        .into_vec()
+        .iter()
+        .map(|value| parse(value, key))
+        .collect()
+}
+
+fn read(path: &Path) -> Result<Option<toml::Table>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let table =
+        toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(table))
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("cargo-rpmstatus").join("config.toml"))
+}
+
+/// The `[<section>.metadata.rpmstatus]` table of a manifest
+fn metadata(manifest: &toml::Table, section: &str, path: &Path) -> Result<Option<Config>, Error> {
+    let Some(table) = manifest
+        .get(section)
+        .and_then(|s| s.get("metadata"))
+        .and_then(|m| m.get("rpmstatus"))
+    else {
+        return Ok(None);
+    };
+    let config = table.clone().try_into::<Config>().with_context(|| {
+        format!(
+            "invalid [{section}.metadata.rpmstatus] in {}",
+            path.display()
+        )
+    })?;
+    Ok(Some(config))
+}
+
+/// The root manifest of the workspace `path` belongs to, found by walking up the parent
+/// directories like cargo does
+fn workspace_root(
+    path: &Path,
+    manifest: &toml::Table,
+) -> Result<Option<(PathBuf, toml::Table)>, Error> {
+    if manifest.contains_key("workspace") {
+        return Ok(Some((path.to_path_buf(), manifest.clone())));
+    }
+
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {}", path.display()))?;
+    for dir in path.ancestors().skip(2) {
+        let candidate = dir.join("Cargo.toml");
+        if let Some(root) = read(&candidate)?
+            && root.contains_key("workspace")
+        {
+            return Ok(Some((candidate, root)));
+        }
+    }
+    Ok(None)
+}
+
+/// `[workspace.metadata.rpmstatus]` of the workspace root, then `[package.metadata.rpmstatus]`
+/// of the package on top
+fn manifest_config(path: &Path) -> Result<Config, Error> {
+    let Some(manifest) = read(path)? else {
+        return Ok(Config::default());
+    };
+
+    let mut config = Config::default();
+    if let Some((root_path, root)) = workspace_root(path, &manifest)?
+        && let Some(layer) = metadata(&root, "workspace", &root_path)?
+    {
+        debug!("Reading workspace config {}", root_path.display());
+        config = config.merge(layer);
+    }
+    if let Some(layer) = metadata(&manifest, "package", path)? {
+        config = config.merge(layer);
+    }
+    Ok(config)
+}
+
+/// Read the user config, then the manifest of the checked project on top
+pub fn load(args: &RpmArgs) -> Result<Config, Error> {
+    let mut config = Config::default();
+
+    if let Some(path) = user_config_path()
+        && let Some(table) = read(&path)?
+    {
+        debug!("Reading user config {}", path.display());
+        let layer = table
+            .try_into::<Config>()
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        config = config.merge(layer);
+    }
+
+    // the manifest inside a .crate archive is only available after unpacking it, without
+    // `--manifest-path` it's the package the current directory belongs to, like for cargo
+    let manifest = match (&args.crate_path, &args.manifest_path) {
+        (Some(_), _) => None,
+        (None, Some(path)) => Some(path.clone()),
+        (None, None) => metadata::locate_project()?,
+    };
+    if let Some(manifest) = manifest {
+        config = config.merge(manifest_config(&manifest)?);
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::{Opts, OutputFormat, RpmStatus};
+    use crate::config::Config;
+    use clap::{CommandFactory, FromArgMatches};
+
+    #[test]
+    fn cli_flags_win() {
+        let user = toml::from_str::<Config>("release = \"rawhide\"\noutput = \"dot\"").unwrap();
+        let project = toml::from_str::<Config>(
+            "release = \"epel9\"\noutput = \"json\"\nexclude = [\"foo@1\"]\nno-dev-dependencies = true",
+        )
+        .unwrap();
+
+        let matches = RpmStatus::command().get_matches_from([
+            "cargo",
+            "rpmstatus",
+            "--rpm-release",
+            "f41,f42",
+        ]);
+        let Opts::Tree(mut args) = RpmStatus::from_arg_matches(&matches).unwrap().cmd else {
+            unreachable!();
+        };
+        user.merge(project.clone())
+            .apply(&mut args, matches.subcommand_matches("rpmstatus").unwrap())
+            .unwrap();
+
+        assert_eq!(args.rpm_release, vec!["f41", "f42"]);
+        assert!(matches!(args.output, OutputFormat::Json));
+        assert_eq!(args.exclude[0].to_string(), "foo@1");
+        assert!(args.no_dev_dependencies);
+
+        let matches =
+            RpmStatus::command().get_matches_from(["cargo", "rpmstatus", "--dev-dependencies"]);
+        let Opts::Tree(mut args) = RpmStatus::from_arg_matches(&matches).unwrap().cmd else {
+            unreachable!();
+        };
+        project
+            .apply(&mut args, matches.subcommand_matches("rpmstatus").unwrap())
+            .unwrap();
+        assert!(!args.no_dev_dependencies);
+    }
+}
@@ -29,6 +29,26 @@ pub enum PkgStatus {
 pub struct PkgInfo {
     pub status: PkgStatus,
     pub version: String,
+    /// Name of the binary RPM providing the crate
+    pub package: String,
+    /// Epoch, version and release of that RPM
+    pub evr: String,
+    /// Architecture of that RPM, e.g. `noarch`
+    pub arch: String,
+    /// Name of the source RPM it was built from
+    pub source_package: String,
+}
+
+/// A row of the `requires` table, with the package it belongs to
+#[derive(Debug, Clone)]
+pub struct Requires {
+    pub package: String,
+    pub source_package: String,
+    /// `crate(foo)` or a rich dependency like `(crate(foo) >= 0.3.0 with crate(foo) < 0.4.0~)`
+    pub name: String,
+    /// `EQ`, `LT`, `LE`, `GT` or `GE` if `version` is set
+    pub flags: Option<String>,
+    pub version: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -115,6 +135,7 @@ pub fn update_rpm_database(rpmrelease: &String) -> Result<()> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&repomd_path)
         .context("could not create or open repomd.xml")?;
     std::io::copy(&mut response.into_reader(), &mut file).context("could not write repomd.xml")?;
@@ -164,13 +185,44 @@ pub fn update_rpm_database(rpmrelease: &String) -> Result<()> {
     Ok(())
 }
 
-fn is_compatible(rpmversion: &str, crateversion: &VersionReq) -> Result<bool, Error> {
+pub fn parse_rpm_version(rpmversion: &str) -> Result<Version, Error> {
     let rpmversion = rpmversion.replace('~', "-");
-    let rpmversion = Version::parse(&rpmversion)?;
+    Ok(Version::parse(&rpmversion)?)
+}
+
+pub fn is_compatible(rpmversion: &str, crateversion: &VersionReq) -> Result<bool, Error> {
+    let rpmversion = parse_rpm_version(rpmversion)?;
 
     Ok(crateversion.matches(&rpmversion))
 }
 
+/// The requirement matching `version` itself and the one matching any semver compatible release
+pub fn requirements(version: &Version) -> Result<(VersionReq, VersionReq), Error> {
+    let semver_version = if version.major == 0 {
+        if version.minor == 0 {
+            format!("{}.{}.{}", version.major, version.minor, version.patch)
+        } else {
+            format!("{}.{}", version.major, version.minor)
+        }
+    } else {
+        format!("{}", version.major)
+    };
+
+    Ok((
+        VersionReq::parse(&version.to_string())?,
+        VersionReq::parse(&semver_version)?,
+    ))
+}
+
+/// Strip version, release and suffix from a source RPM file name,
+/// e.g. `rust-anyhow-1.0.80-1.fc42.src.rpm`
+pub fn source_package_name(sourcerpm: &str) -> Option<String> {
+    let nvr = sourcerpm.strip_suffix(".src.rpm")?;
+    let (nv, _release) = nvr.rsplit_once('-')?;
+    let (name, _version) = nv.rsplit_once('-')?;
+    Some(name.to_string())
+}
+
 pub struct Connection {
     sock: SqliteCon,
 }
@@ -193,7 +245,7 @@ impl Connection {
         // config.shell().status("Querying", format!("sid: {}", package))?;
         info!("Querying: {}", package);
         let info = self.search_generic(
-            "SELECT version FROM packages WHERE name LIKE ?1;",
+            "SELECT version, name, epoch, release, rpm_sourcerpm, arch FROM packages WHERE name LIKE ?1;",
             package,
             version,
         )?;
@@ -201,6 +253,55 @@ impl Connection {
         Ok(info)
     }
 
+    /// Versions of every package in the release providing `name`, e.g. `crate(serde/default)`
+    pub fn provides(&mut self, name: &str) -> Result<Vec<String>, Error> {
+        let mut statement = self
+            .sock
+            .prepare("SELECT version FROM provides WHERE name = ?1;")?;
+        let versions = statement
+            .query_map([name], |row| row.get::<_, Option<String>>(0))?
+            .filter_map(|version| version.ok().flatten())
+            .collect();
+        Ok(versions)
+    }
+
+    /// Every requirement on `crate(name)` or one of its features, including rich dependencies
+    pub fn requires(&mut self, name: &str) -> Result<Vec<Requires>, Error> {
+        // prefix matches can only use the index on requires.name if LIKE is case sensitive.
+        // `_` in crate names matches any character, the caller filters the extra rows.
+        self.sock.pragma_update(None, "case_sensitive_like", true)?;
+        let rows = self.query_requires(name);
+        self.sock
+            .pragma_update(None, "case_sensitive_like", false)?;
+        rows
+    }
+
+    fn query_requires(&mut self, name: &str) -> Result<Vec<Requires>, Error> {
+        let mut statement = self.sock.prepare(
+            "SELECT p.name, p.rpm_sourcerpm, r.name, r.flags, r.version FROM requires r \
+             JOIN packages p ON p.pkgKey = r.pkgKey WHERE r.name LIKE ?1 OR r.name LIKE ?2;",
+        )?;
+        let rows = statement
+            .query_map(
+                [format!("crate({name}%"), format!("(crate({name}%")],
+                |row| {
+                    Ok(Requires {
+                        package: row.get(0)?,
+                        source_package: row
+                            .get::<_, Option<String>>(1)?
+                            .as_deref()
+                            .and_then(source_package_name)
+                            .unwrap_or_default(),
+                        name: row.get(2)?,
+                        flags: row.get(3)?,
+                        version: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
     pub fn search_generic(
         &mut self,
         query: &str,
@@ -210,25 +311,25 @@ impl Connection {
         let mut info = PkgInfo {
             status: PkgStatus::NotFound,
             version: String::new(),
-        };
-        let semver_version = if version.major == 0 {
-            if version.minor == 0 {
-                format!("{}.{}.{}", version.major, version.minor, version.patch)
-            } else {
-                format!("{}.{}", version.major, version.minor)
-            }
-        } else {
-            format!("{}", version.major)
+            package: String::new(),
+            evr: String::new(),
+            arch: String::new(),
+            source_package: String::new(),
         };
         let mut statement = self.sock.prepare(query)?;
-        let rpm_version_iter = statement
-            .query_map([format!("rust-{package}%").as_str()], |row| {
-                Ok(row.get::<_, String>(0)?)
+        let rpm_version_iter =
+            statement.query_map([format!("rust-{package}%").as_str()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
             })?;
 
-        let version = version.to_string();
-        let version = VersionReq::parse(&version)?;
-        let semver_version = VersionReq::parse(&semver_version)?;
+        let (version, semver_version) = requirements(version)?;
         for rpm_version in rpm_version_iter {
             if let Err(err) = rpm_version {
                 error!(
@@ -238,19 +339,34 @@ impl Connection {
                 continue;
             }
 
-            let rpm_version = rpm_version.unwrap();
+            let (rpm_version, rpm_name, epoch, release, sourcerpm, arch) = rpm_version.unwrap();
 
-            if is_compatible(rpm_version.as_str(), &version)? {
-                info.version = rpm_version;
-                info.status = PkgStatus::Found;
-                debug!("{package} {:?}", info);
-                return Ok(info);
+            let status = if is_compatible(rpm_version.as_str(), &version)? {
+                PkgStatus::Found
             } else if is_compatible(rpm_version.as_str(), &semver_version)? {
-                info.version = rpm_version;
-                info.status = PkgStatus::Compatible;
+                PkgStatus::Compatible
             } else if info.status == PkgStatus::NotFound {
-                info.version = rpm_version;
-                info.status = PkgStatus::Outdated;
+                PkgStatus::Outdated
+            } else {
+                continue;
+            };
+
+            info.evr = match epoch.as_deref() {
+                None | Some("0") | Some("") => format!("{rpm_version}-{release}"),
+                Some(epoch) => format!("{epoch}:{rpm_version}-{release}"),
+            };
+            info.source_package = sourcerpm
+                .as_deref()
+                .and_then(source_package_name)
+                .unwrap_or_default();
+            info.version = rpm_version;
+            info.package = rpm_name;
+            info.arch = arch.unwrap_or_default();
+            info.status = status;
+
+            if info.status == PkgStatus::Found {
+                debug!("{package} {:?}", info);
+                return Ok(info);
             }
         }
 
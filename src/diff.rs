@@ -0,0 +1,252 @@
+use crate::args::{Charset, DiffArgs, RpmArgs};
+use crate::errors::*;
+use crate::fedora::{self, Pkg};
+use crate::format::Pattern;
+use crate::graph::{self, Graph};
+use crate::metadata;
+use crate::registry::semver_epoch;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub fn run(args: &RpmArgs, diff: &DiffArgs) -> Result<(), Error> {
+    let format = Pattern::new(&args.format)?;
+    let arrow = match args.charset {
+        Charset::Utf8 => "→",
+        Charset::Ascii => "->",
+    };
+
+    let is_crate = |path: &Path| path.extension().is_some_and(|ext| ext == "crate");
+    let mut old = match (is_crate(&diff.old), is_crate(&diff.new)) {
+        (true, true) => crate_graph(args, &diff.old)?,
+        (false, false) => {
+            if !args.edges.is_empty()
+                || args.no_dev_dependencies
+                || args.target.is_some()
+                || args.all_targets
+            {
+                bail!(
+                    "Cargo.lock records neither dependency kinds nor targets, compare two .crate \
+                     archives to use --edges, --no-dev-dependencies, --target or --all-targets"
+                );
+            }
+            lockfile_graph(args, &diff.old)?
+        }
+        _ => bail!("can only compare two Cargo.lock files or two .crate archives"),
+    };
+    let mut new = if is_crate(&diff.new) {
+        crate_graph(args, &diff.new)?
+    } else {
+        lockfile_graph(args, &diff.new)?
+    };
+
+    info!("Populating with packaging data");
+    fedora::populate(args, &mut old, args.rpm_release.first().cloned())?;
+    fedora::populate(args, &mut new, args.rpm_release.first().cloned())?;
+
+    let changes = compare(&old, &new);
+    if changes.is_empty() {
+        println!("No changes in packaging work");
+        return Ok(());
+    }
+
+    let titles = [
+        "Newly required crates:",
+        "Bumped across a semver boundary:",
+        "Dropped crates:",
+    ];
+    for (i, title) in titles.iter().enumerate() {
+        let section = changes
+            .iter()
+            .filter(|change| change.section() == i)
+            .collect::<Vec<_>>();
+        if section.is_empty() {
+            continue;
+        }
+
+        println!("{title}");
+        for change in section {
+            match change {
+                Change::New(pkg) | Change::Dropped(pkg) => {
+                    println!(" {} {}", pkg.packaging_status(), format.display(pkg))
+                }
+                Change::Bumped { from, to } => println!(
+                    " {} {} v{} {} {}",
+                    to.packaging_status(),
+                    from.name,
+                    from.version,
+                    arrow,
+                    format.display(to)
+                ),
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn crate_graph(args: &RpmArgs, path: &Path) -> Result<Graph, Error> {
+    let mut args = args.clone();
+    args.crate_path = Some(path.to_path_buf());
+
+    info!("Reading metadata of {}", path.display());
+    let metadata = metadata::get(&args)?;
+    graph::build(&args, metadata)
+}
+
+fn lockfile_graph(args: &RpmArgs, path: &Path) -> Result<Graph, Error> {
+    info!("Reading {}", path.display());
+    let lockfile = metadata::read_lockfile(path)?;
+    graph::from_lockfile(args, path, lockfile)
+}
+
+pub enum Change<'a> {
+    New(&'a Pkg),
+    Bumped { from: &'a Pkg, to: &'a Pkg },
+    Dropped(&'a Pkg),
+}
+
+impl Change<'_> {
+    fn package(&self) -> &Pkg {
+        match self {
+            Change::New(pkg) | Change::Dropped(pkg) => pkg,
+            Change::Bumped { to, .. } => to,
+        }
+    }
+
+    fn section(&self) -> usize {
+        match self {
+            Change::New(_) => 0,
+            Change::Bumped { .. } => 1,
+            Change::Dropped(_) => 2,
+        }
+    }
+}
+
+/// Group the registry and git crates of a graph by name, sorted by version
+fn by_name(graph: &Graph) -> BTreeMap<&str, Vec<&Pkg>> {
+    let mut packages: BTreeMap<&str, Vec<&Pkg>> = BTreeMap::new();
+    for pkg in graph.graph.node_weights() {
+        // path dependencies are part of the project itself
+        if pkg.source.is_some() {
+            packages.entry(&pkg.name).or_default().push(pkg);
+        }
+    }
+    for versions in packages.values_mut() {
+        versions.sort_by_key(|pkg| &pkg.version);
+        versions.dedup_by_key(|pkg| &pkg.version);
+    }
+    packages
+}
+
+pub fn compare<'a>(old: &'a Graph, new: &'a Graph) -> Vec<Change<'a>> {
+    let old = by_name(old);
+    let new = by_name(new);
+
+    let mut changes = vec![];
+    for (name, versions) in &new {
+        let Some(previous) = old.get(name) else {
+            changes.extend(versions.iter().map(|pkg| Change::New(pkg)));
+            continue;
+        };
+
+        let retained = |pkg: &Pkg| {
+            let epoch = semver_epoch(&pkg.version);
+            versions.iter().any(|p| semver_epoch(&p.version) == epoch)
+        };
+        // a bump replaces the newest version that isn't required anymore
+        let replaced = previous
+            .iter()
+            .rev()
+            .find(|pkg| !retained(pkg))
+            .unwrap_or_else(|| previous.last().unwrap());
+
+        let mut bumped = false;
+        for pkg in versions {
+            let epoch = semver_epoch(&pkg.version);
+            if !previous.iter().any(|p| semver_epoch(&p.version) == epoch) {
+                bumped = true;
+                changes.push(Change::Bumped {
+                    from: replaced,
+                    to: pkg,
+                });
+            }
+        }
+
+        for pkg in previous {
+            if retained(pkg) || (bumped && pkg.version == replaced.version) {
+                continue;
+            }
+            changes.push(Change::Dropped(pkg));
+        }
+    }
+
+    for (name, versions) in &old {
+        if !new.contains_key(name) {
+            changes.extend(versions.iter().map(|pkg| Change::Dropped(pkg)));
+        }
+    }
+
+    changes.sort_by(|a, b| (a.section(), &a.package().name).cmp(&(b.section(), &b.package().name)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::RpmArgs;
+    use crate::diff::{compare, Change};
+    use crate::graph::{self, Graph};
+    use std::path::Path;
+
+    fn lockfile(packages: &[(&str, &str)]) -> Graph {
+        let mut content = String::new();
+        for (name, version) in packages {
+            content.push_str(&format!(
+                "[[package]]\nname = \"{name}\"\nversion = \"{version}\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n"
+            ));
+        }
+        let lockfile = toml::from_str(&content).unwrap();
+        graph::from_lockfile(&RpmArgs::default(), Path::new("Cargo.lock"), lockfile).unwrap()
+    }
+
+    #[test]
+    fn compare_lockfiles() {
+        let old = lockfile(&[
+            ("a", "1.0.0"),
+            ("b", "0.3.1"),
+            ("c", "1.2.0"),
+            ("d", "2.0.0"),
+            ("f", "0.2.0"),
+            ("f", "0.3.0"),
+        ]);
+        let new = lockfile(&[
+            ("a", "1.4.0"),
+            ("b", "0.4.0"),
+            ("c", "1.2.0"),
+            ("e", "0.1.0"),
+            ("f", "0.4.0"),
+        ]);
+
+        let changes = compare(&old, &new)
+            .iter()
+            .map(|change| match change {
+                Change::New(pkg) => format!("new {} {}", pkg.name, pkg.version),
+                Change::Bumped { from, to } => {
+                    format!("bumped {} {} {}", to.name, from.version, to.version)
+                }
+                Change::Dropped(pkg) => format!("dropped {} {}", pkg.name, pkg.version),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                "new e 0.1.0",
+                "bumped b 0.3.1 0.4.0",
+                "bumped f 0.3.0 0.4.0",
+                "dropped d 2.0.0",
+                "dropped f 0.2.0",
+            ]
+        );
+    }
+}
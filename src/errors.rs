@@ -1,2 +1,2 @@
 pub use anyhow::{bail, Context, Error, Result};
-pub use log::{debug, info};
+pub use log::{debug, info, warn};
@@ -1,10 +1,16 @@
+use crate::args::{AssumePackaged, RpmArgs};
 use crate::db::{self, Connection, PkgStatus};
 use crate::errors::*;
 use crate::graph::Graph;
-use cargo_metadata::{Package, PackageId, Source};
+use crate::local::{self, LocalRepo};
+use crate::registry::Freshness;
+use crate::spec;
+use crate::style;
+use cargo_metadata::{Dependency, Package, PackageId, Source};
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
 use semver::Version;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::thread;
 
@@ -19,34 +25,174 @@ pub struct Pkg {
     pub manifest_path: PathBuf,
     pub license: Option<String>,
     pub repository: Option<String>,
+    pub proc_macro: bool,
+    /// Whether `publish` allows uploading the package to crates.io
+    pub publish: bool,
+    pub dependencies: Vec<Dependency>,
+    pub feature_map: BTreeMap<String, Vec<String>>,
+    /// Features enabled in the resolved build
+    pub features: Vec<String>,
 
     pub rpmrelease: String,
     pub rpminfo: Option<RpmInfo>,
+    pub upstream: Option<Version>,
+    /// Only required on platforms Fedora doesn't build for
+    pub patch_out: bool,
+    pub blocker: Option<Blocker>,
 }
 
+/// A source that can't be used for Fedora packaging, which only builds from crates.io releases
+#[derive(Debug, Clone)]
+pub enum Blocker {
+    Git {
+        url: String,
+        commit: Option<String>,
+        patched: bool,
+    },
+    Path {
+        path: PathBuf,
+        patched: bool,
+    },
+    Registry {
+        url: String,
+        patched: bool,
+    },
+}
+
+impl Blocker {
+    /// Classify a package source, `member` packages are part of the project itself
+    pub fn detect(pkg: &Pkg, member: bool, patched: bool) -> Option<Blocker> {
+        match &pkg.source {
+            Some(source) if source.is_crates_io() => None,
+            Some(source) => {
+                if let Some(git) = source.repr.strip_prefix("git+") {
+                    let (url, commit) = match git.split_once('#') {
+                        Some((url, commit)) => (url, Some(commit.to_string())),
+                        None => (git, None),
+                    };
+                    Some(Blocker::Git {
+                        url: url.to_string(),
+                        commit,
+                        patched,
+                    })
+                } else {
+                    let url = source
+                        .repr
+                        .split_once('+')
+                        .map_or(source.repr.as_str(), |(_, url)| url);
+                    Some(Blocker::Registry {
+                        url: url.to_string(),
+                        patched,
+                    })
+                }
+            }
+            None if member && !patched => None,
+            None => Some(Blocker::Path {
+                path: pkg
+                    .manifest_path
+                    .parent()
+                    .map(PathBuf::from)
+                    .unwrap_or_default(),
+                patched,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Blocker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let patched = match self {
+            Blocker::Git {
+                url,
+                commit,
+                patched,
+            } => {
+                write!(f, "git {url}")?;
+                if let Some(commit) = commit {
+                    write!(f, " at {commit}")?;
+                }
+                patched
+            }
+            Blocker::Path { path, patched } => {
+                write!(f, "path {}", path.display())?;
+                patched
+            }
+            Blocker::Registry { url, patched } => {
+                write!(f, "registry {url}")?;
+                patched
+            }
+        };
+        if *patched {
+            write!(f, " via [patch]")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PackagingProgress {
     Available,
+    Compatible,
+    Pending,
+    SpecPending,
     NeedsUpdate,
     Missing,
+    PatchOut,
+    Blocker,
 }
 
 use std::fmt;
+use std::str::FromStr;
+
+impl PackagingProgress {
+    /// The status as a single word, as accepted by `--only`
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackagingProgress::Available => "available",
+            PackagingProgress::Compatible => "compatible",
+            PackagingProgress::Pending => "pending",
+            PackagingProgress::SpecPending => "spec-pending",
+            PackagingProgress::NeedsUpdate => "outdated",
+            PackagingProgress::Missing => "missing",
+            PackagingProgress::PatchOut => "patch-out",
+            PackagingProgress::Blocker => "blocker",
+        }
+    }
+}
+
+impl FromStr for PackagingProgress {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<PackagingProgress, &'static str> {
+        match s {
+            "available" => Ok(PackagingProgress::Available),
+            "compatible" => Ok(PackagingProgress::Compatible),
+            "pending" => Ok(PackagingProgress::Pending),
+            "spec-pending" => Ok(PackagingProgress::SpecPending),
+            "outdated" => Ok(PackagingProgress::NeedsUpdate),
+            "missing" => Ok(PackagingProgress::Missing),
+            "patch-out" => Ok(PackagingProgress::PatchOut),
+            "blocker" => Ok(PackagingProgress::Blocker),
+            _ => Err("invalid packaging status"),
+        }
+    }
+}
 
 impl fmt::Display for PackagingProgress {
     //! Generate icons to display the packaging progress.
     //! They should all take the same width when printed in a terminal
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let icon = match self {
-            PackagingProgress::Available => "  ",
-            PackagingProgress::NeedsUpdate => "⌛",
-            PackagingProgress::Missing => "🔴",
-        };
-        write!(f, "{}", icon)
+        write!(f, "{}", style::icon(*self))
     }
 }
 
 impl Pkg {
     pub fn new(pkg: Package) -> Pkg {
+        let proc_macro = pkg.targets.iter().any(|target| target.is_proc_macro());
+        let publish = pkg
+            .publish
+            .is_none_or(|registries| registries.iter().any(|r| r == "crates-io"));
+
         Pkg {
             id: pkg.id,
             name: pkg.name,
@@ -55,9 +201,47 @@ impl Pkg {
             manifest_path: pkg.manifest_path.into(),
             license: pkg.license,
             repository: pkg.repository,
+            proc_macro,
+            publish,
+            dependencies: pkg.dependencies,
+            feature_map: pkg.features,
+            features: vec![],
+
+            rpmrelease: String::from("rawhide"),
+            rpminfo: None,
+            upstream: None,
+            patch_out: false,
+            blocker: None,
+        }
+    }
+
+    /// A package known only from a Cargo.lock entry
+    pub fn locked(
+        id: PackageId,
+        name: String,
+        version: Version,
+        source: Option<Source>,
+        manifest_path: PathBuf,
+    ) -> Pkg {
+        Pkg {
+            id,
+            name,
+            version,
+            source,
+            manifest_path,
+            license: None,
+            repository: None,
+            proc_macro: false,
+            publish: true,
+            dependencies: vec![],
+            feature_map: BTreeMap::new(),
+            features: vec![],
 
             rpmrelease: String::from("rawhide"),
             rpminfo: None,
+            upstream: None,
+            patch_out: false,
+            blocker: None,
         }
     }
 
@@ -70,6 +254,23 @@ impl Pkg {
     }
 
     pub fn show_dependencies(&self) -> bool {
+        if self.patch_out {
+            return false;
+        }
+
+        if self.blocker.is_some() {
+            return true;
+        }
+
+        // assumed to be packaged soon, so its dependencies are taken care of
+        if self
+            .rpminfo
+            .as_ref()
+            .is_some_and(|rpm| rpm.pending || rpm.spec.is_some())
+        {
+            return false;
+        }
+
         if !self.in_fedora() {
             return true;
         }
@@ -81,12 +282,41 @@ impl Pkg {
         }
     }
 
+    /// Where the rpm was found, the release or `local` for `--local-rpms`
+    pub fn repo(&self) -> &str {
+        match &self.rpminfo {
+            Some(rpm) if rpm.local => "local",
+            _ => &self.rpmrelease,
+        }
+    }
+
+    /// Compare the Fedora version against the newest upstream release, if both are known
+    pub fn freshness(&self) -> Option<Freshness> {
+        let rpm = self.rpminfo.as_ref().filter(|rpm| rpm.in_rawhide)?;
+        let upstream = self.upstream.as_ref()?;
+        let packaged = db::parse_rpm_version(&rpm.version).ok()?;
+
+        Some(Freshness::compare(&packaged, upstream))
+    }
+
     pub fn packaging_status(&self) -> PackagingProgress {
+        if self.patch_out {
+            return PackagingProgress::PatchOut;
+        }
+
+        if self.blocker.is_some() {
+            return PackagingProgress::Blocker;
+        }
+
         if let Some(rpm) = &self.rpminfo {
-            if rpm.in_rawhide {
+            if rpm.pending {
+                PackagingProgress::Pending
+            } else if rpm.spec.is_some() {
+                PackagingProgress::SpecPending
+            } else if rpm.in_rawhide {
                 if rpm.compatible {
                     // Available at an older yet compatible version
-                    PackagingProgress::Available
+                    PackagingProgress::Compatible
                 } else if rpm.outdated {
                     PackagingProgress::NeedsUpdate
                 } else {
@@ -110,6 +340,20 @@ pub struct RpmInfo {
     pub compatible: bool,
     pub exact_match: bool,
     pub version: String,
+    /// The binary RPM providing the crate and its epoch-version-release
+    pub package: String,
+    pub evr: String,
+    pub arch: String,
+    /// The source RPM it was built from
+    pub source_package: String,
+    /// Not in the repository yet, but assumed to be packaged soon with `--assume-packaged`
+    pub pending: bool,
+    /// Why the crate is assumed to be packaged, e.g. a review bug
+    pub note: Option<String>,
+    /// Provided by a locally built rpm from `--local-rpms` rather than the repository
+    pub local: bool,
+    /// Work-in-progress spec from `--spec-dir` packaging the crate
+    pub spec: Option<PathBuf>,
 }
 
 fn run_task(db: &mut Connection, pkg: Pkg) -> Result<RpmInfo> {
@@ -119,12 +363,24 @@ fn run_task(db: &mut Connection, pkg: Pkg) -> Result<RpmInfo> {
         compatible: false,
         exact_match: false,
         version: String::new(),
+        package: String::new(),
+        evr: String::new(),
+        arch: String::new(),
+        source_package: String::new(),
+        pending: false,
+        note: None,
+        local: false,
+        spec: None,
     };
 
     let info = db.search(&pkg.name, &pkg.version)?;
     if info.status != PkgStatus::NotFound {
         rpm.in_rawhide = true;
         rpm.version = info.version;
+        rpm.package = info.package;
+        rpm.evr = info.evr;
+        rpm.arch = info.arch;
+        rpm.source_package = info.source_package;
     }
 
     match info.status {
@@ -137,7 +393,11 @@ fn run_task(db: &mut Connection, pkg: Pkg) -> Result<RpmInfo> {
     Ok(rpm)
 }
 
-pub fn populate(graph: &mut Graph, rpmrelease: Option<String>) -> Result<(), Error> {
+pub fn populate(
+    args: &RpmArgs,
+    graph: &mut Graph,
+    rpmrelease: Option<String>,
+) -> Result<(), Error> {
     let rpmrelease = rpmrelease.unwrap_or(String::from("rawhide"));
 
     info!("Updating {} release database", rpmrelease);
@@ -208,5 +468,38 @@ pub fn populate(graph: &mut Graph, rpmrelease: Option<String>) -> Result<(), Err
 
     pb.finish_and_clear();
 
+    if let Some(dir) = &args.local_rpms {
+        let repo = LocalRepo::load(dir)?;
+        local::populate(graph, &repo)?;
+    }
+    assume_packaged(graph, &args.assume_packaged);
+    if let Some(dir) = &args.spec_dir {
+        let specs = spec::load(dir)?;
+        spec::populate(graph, &specs);
+        graph.unsatisfied_specs = spec::unsatisfied(&specs, &rpmrelease)?;
+    }
+
     Ok(())
 }
+
+/// Mark the crates given with `--assume-packaged` as pending, unless the repository caught up
+fn assume_packaged(graph: &mut Graph, assumed: &[AssumePackaged]) {
+    for pkg in graph.graph.node_weights_mut() {
+        let Some(assumption) = assumed.iter().rev().find(|a| a.spec.matches(pkg)) else {
+            continue;
+        };
+        let Some(rpm) = &mut pkg.rpminfo else {
+            continue;
+        };
+
+        if rpm.in_rawhide && !rpm.outdated {
+            debug!(
+                "{} v{} is already packaged in {}, ignoring --assume-packaged {}",
+                pkg.name, pkg.version, pkg.rpmrelease, assumption.spec
+            );
+            continue;
+        }
+        rpm.pending = true;
+        rpm.note = assumption.note.clone();
+    }
+}
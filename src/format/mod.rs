@@ -1,16 +1,58 @@
-use crate::fedora::Pkg;
-use crate::format::parse::{Parser, RawChunk};
+use crate::fedora::{Pkg, RpmInfo};
+use crate::format::parse::{Align, Parser, RawChunk, Spec};
+use crate::style;
 use anyhow::{anyhow, Error};
+use cargo_metadata::DependencyKind;
 use colored::Colorize;
-use std::fmt;
+use semver::VersionReq;
+use std::fmt::{self, Write};
+use std::mem;
 
 mod parse;
 
-enum Chunk {
-    Raw(String),
+enum Field {
     Package,
     License,
     Repository,
+    Name,
+    Version,
+    Status,
+    RpmVersion,
+    Release,
+    Rpm,
+    Requirement,
+    Kind,
+    Depth,
+    Features,
+}
+
+impl Field {
+    fn new(name: &str) -> Result<Field, Error> {
+        let field = match name {
+            "p" => Field::Package,
+            "l" => Field::License,
+            "r" => Field::Repository,
+            "n" => Field::Name,
+            "v" => Field::Version,
+            "s" => Field::Status,
+            "rv" => Field::RpmVersion,
+            "rel" => Field::Release,
+            "rpm" => Field::Rpm,
+            "req" => Field::Requirement,
+            "k" => Field::Kind,
+            "d" => Field::Depth,
+            "f" => Field::Features,
+            _ => return Err(anyhow!("unsupported pattern `{}`", name)),
+        };
+        Ok(field)
+    }
+}
+
+enum Chunk {
+    Raw(String),
+    Field(Field, Spec),
+    /// Only shown if the field isn't empty
+    Conditional(Field, Vec<Chunk>),
 }
 
 pub struct Pattern(Vec<Chunk>);
@@ -18,15 +60,20 @@ pub struct Pattern(Vec<Chunk>);
 impl Pattern {
     pub fn new(format: &str) -> Result<Pattern, Error> {
         let mut chunks = vec![];
+        let mut sections = vec![];
 
         for raw in Parser::new(format) {
             let chunk = match raw {
                 RawChunk::Text(text) => Chunk::Raw(text.to_owned()),
-                RawChunk::Argument("p") => Chunk::Package,
-                RawChunk::Argument("l") => Chunk::License,
-                RawChunk::Argument("r") => Chunk::Repository,
-                RawChunk::Argument(ref a) => {
-                    return Err(anyhow!("unsupported pattern `{}`", a));
+                RawChunk::Argument(name, spec) => Chunk::Field(Field::new(name)?, spec),
+                RawChunk::Conditional(name) => {
+                    sections.push((Field::new(name)?, mem::take(&mut chunks)));
+                    continue;
+                }
+                RawChunk::End => {
+                    let (field, outer) =
+                        sections.pop().ok_or_else(|| anyhow!("unexpected '}}'"))?;
+                    Chunk::Conditional(field, mem::replace(&mut chunks, outer))
                 }
                 RawChunk::Error(err) => return Err(anyhow!("{}", err)),
             };
@@ -37,55 +84,106 @@ impl Pattern {
     }
 
     pub fn display<'a>(&'a self, package: &'a Pkg) -> Display<'a> {
+        self.display_at(package, Position::default())
+    }
+
+    /// Display a package at a position in a dependency tree
+    pub fn display_at<'a>(&'a self, package: &'a Pkg, position: Position<'a>) -> Display<'a> {
         Display {
             pattern: self,
             package,
+            position,
         }
     }
 }
 
+/// Where a package appears in the tree, for the placeholders describing the edge leading to it
+#[derive(Clone, Copy, Default)]
+pub struct Position<'a> {
+    pub requirement: Option<&'a VersionReq>,
+    pub kind: Option<DependencyKind>,
+    pub depth: usize,
+}
+
 pub struct Display<'a> {
     pattern: &'a Pattern,
     package: &'a Pkg,
+    position: Position<'a>,
 }
 
-impl<'a> fmt::Display for Display<'a> {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for chunk in &self.pattern.0 {
-            match *chunk {
-                Chunk::Raw(ref s) => fmt.write_str(s)?,
-                Chunk::Package => {
-                    let pkg = format!("{} v{}", self.package.name, self.package.version);
-                    if let Some(deb) = &self.package.rpminfo {
-                        if deb.in_rawhide {
-                            if deb.compatible {
-                                write!(
-                                    fmt,
-                                    "{} ({} in {})",
-                                    pkg.green(),
-                                    deb.version.yellow(),
-                                    self.package.rpmrelease
-                                )?;
-                            } else if deb.outdated {
-                                write!(
-                                    fmt,
-                                    "{} (outdated, {} in {})",
-                                    pkg.yellow(),
-                                    deb.version.red(),
-                                    self.package.rpmrelease
-                                )?;
-                            } else {
-                                write!(fmt, "{} (in {})", pkg.green(), self.package.rpmrelease)?;
-                            }
+impl Display<'_> {
+    fn write_chunks(&self, chunks: &[Chunk], out: &mut String) -> fmt::Result {
+        for chunk in chunks {
+            match chunk {
+                Chunk::Raw(s) => out.push_str(s),
+                Chunk::Field(field, spec) => {
+                    let mut value = String::new();
+                    self.write_field(field, &mut value)?;
+                    pad(out, &value, spec);
+                }
+                Chunk::Conditional(field, section) => {
+                    let mut value = String::new();
+                    self.write_field(field, &mut value)?;
+                    if !value.is_empty() {
+                        self.write_chunks(section, out)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_field(&self, field: &Field, fmt: &mut String) -> fmt::Result {
+        match field {
+            Field::Package => {
+                let pkg = format!("{} v{}", self.package.name, self.package.version);
+                let pkg = style::link(crate_url(self.package).as_deref(), &pkg);
+                let pkg = style::paint(self.package.packaging_status(), &pkg);
+                if self.package.patch_out {
+                    write!(fmt, "{pkg} (patch out)")?;
+                } else if let Some(deb) = &self.package.rpminfo {
+                    let rpm_version = style::link(fedora_url(deb).as_deref(), &deb.version);
+                    if deb.pending {
+                        match &deb.note {
+                            Some(note) => write!(fmt, "{} (pending, {})", pkg, note.cyan())?,
+                            None => write!(fmt, "{pkg} (pending)")?,
+                        }
+                    } else if let Some(spec) = &deb.spec {
+                        let file = spec.file_name().unwrap_or_default().to_string_lossy();
+                        write!(fmt, "{} (spec pending, {})", pkg, file.cyan())?;
+                    } else if deb.in_rawhide {
+                        if deb.compatible {
+                            write!(
+                                fmt,
+                                "{} ({} in {})",
+                                pkg,
+                                rpm_version.yellow(),
+                                self.package.repo()
+                            )?;
                         } else if deb.outdated {
-                            write!(fmt, "{} (outdated, {})", pkg.red(), deb.version.red())?;
+                            write!(
+                                fmt,
+                                "{} (outdated, {} in {})",
+                                pkg,
+                                rpm_version.red(),
+                                self.package.repo()
+                            )?;
                         } else {
-                            write!(fmt, "{pkg}")?;
+                            write!(fmt, "{} (in {})", pkg, self.package.repo())?;
                         }
+                    } else if deb.outdated {
+                        write!(fmt, "{} (outdated, {})", pkg, rpm_version.red())?;
                     } else {
                         write!(fmt, "{pkg}")?;
                     }
+                } else {
+                    write!(fmt, "{pkg}")?;
+                }
 
+                if let Some(blocker) = &self.package.blocker {
+                    write!(fmt, " ({})", blocker.to_string().red())?;
+                } else {
                     match &self.package.source {
                         Some(source) if !source.is_crates_io() => write!(fmt, " ({source})")?,
                         // https://github.com/rust-lang/cargo/issues/7483
@@ -97,19 +195,146 @@ impl<'a> fmt::Display for Display<'a> {
                         _ => {}
                     }
                 }
-                Chunk::License => {
-                    if let Some(ref license) = self.package.license {
-                        write!(fmt, "{license}")?
+
+                if let Some(freshness) = self.package.freshness()
+                    && freshness.needs_bump()
+                    && let Some(upstream) = &self.package.upstream
+                {
+                    write!(
+                        fmt,
+                        " (upstream v{}, {})",
+                        upstream,
+                        freshness.to_string().cyan()
+                    )?;
+                }
+            }
+            Field::License => {
+                if let Some(ref license) = self.package.license {
+                    write!(fmt, "{license}")?
+                }
+            }
+            Field::Repository => {
+                if let Some(ref repository) = self.package.repository {
+                    write!(fmt, "{repository}")?
+                }
+            }
+            Field::Name => write!(
+                fmt,
+                "{}",
+                style::link(crate_url(self.package).as_deref(), &self.package.name)
+            )?,
+            Field::Version => write!(fmt, "{}", self.package.version)?,
+            Field::Status => write!(fmt, "{}", self.package.packaging_status().name())?,
+            Field::RpmVersion => {
+                if let Some(rpm) = &self.package.rpminfo {
+                    write!(
+                        fmt,
+                        "{}",
+                        style::link(fedora_url(rpm).as_deref(), &rpm.version)
+                    )?
+                }
+            }
+            Field::Release => write!(fmt, "{}", self.package.rpmrelease)?,
+            Field::Rpm => {
+                if let Some(rpm) = &self.package.rpminfo {
+                    write!(fmt, "{}", rpm.package)?
+                }
+            }
+            Field::Requirement => {
+                if let Some(requirement) = self.position.requirement {
+                    write!(fmt, "{requirement}")?
+                }
+            }
+            Field::Kind => match self.position.kind {
+                Some(DependencyKind::Build) => write!(fmt, "build")?,
+                Some(DependencyKind::Development) => write!(fmt, "dev")?,
+                Some(_) => write!(fmt, "normal")?,
+                None => {}
+            },
+            Field::Depth => write!(fmt, "{}", self.position.depth)?,
+            Field::Features => write!(fmt, "{}", self.package.features.join(","))?,
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write_chunks(&self.pattern.0, &mut out)?;
+        fmt.write_str(&out)
+    }
+}
+
+/// The crates.io page of a release, or the repository of other crates
+fn crate_url(pkg: &Pkg) -> Option<String> {
+    match &pkg.source {
+        Some(source) if source.is_crates_io() => Some(format!(
+            "https://crates.io/crates/{}/{}",
+            pkg.name, pkg.version
+        )),
+        _ => pkg.repository.clone(),
+    }
+}
+
+/// The dist-git page of the source RPM providing a crate
+fn fedora_url(rpm: &RpmInfo) -> Option<String> {
+    // local builds may not have a dist-git repository yet
+    (!rpm.source_package.is_empty() && !rpm.local)
+        .then(|| format!("https://src.fedoraproject.org/rpms/{}", rpm.source_package))
+}
+
+/// Number of characters shown in a terminal, ignoring color and hyperlink escape sequences
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            if chars.peek() == Some(&']') {
+                // operating system command, terminated by ESC \
+                while let Some(ch) = chars.next() {
+                    if ch == '\x1b' && chars.next_if_eq(&'\\').is_some() {
+                        break;
                     }
                 }
-                Chunk::Repository => {
-                    if let Some(ref repository) = self.package.repository {
-                        write!(fmt, "{repository}")?
+            } else {
+                for ch in chars.by_ref() {
+                    if ch.is_ascii_alphabetic() {
+                        break;
                     }
                 }
             }
+        } else {
+            width += 1;
         }
+    }
+    width
+}
 
-        Ok(())
+fn pad(out: &mut String, value: &str, spec: &Spec) {
+    let fill = spec.width.saturating_sub(visible_width(value));
+    let (before, after) = match spec.align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    };
+    out.extend(std::iter::repeat_n(' ', before));
+    out.push_str(value);
+    out.extend(std::iter::repeat_n(' ', after));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::visible_width;
+
+    #[test]
+    fn width_ignores_escape_sequences() {
+        assert_eq!(visible_width("serde"), 5);
+        assert_eq!(visible_width("\x1b[32mserde\x1b[0m"), 5);
+        assert_eq!(
+            visible_width("\x1b]8;;https://crates.io/crates/serde\x1b\\serde\x1b]8;;\x1b\\"),
+            5
+        );
     }
 }
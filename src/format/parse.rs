@@ -3,13 +3,34 @@ use std::str;
 
 pub enum RawChunk<'a> {
     Text(&'a str),
-    Argument(&'a str),
+    Argument(&'a str, Spec),
+    /// Start of a section only shown if the argument isn't empty, e.g. `{?r|`
+    Conditional(&'a str),
+    /// The `}` closing a conditional section
+    End,
     Error(&'static str),
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Width and alignment of an argument, e.g. `{n:>30}`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Spec {
+    pub align: Align,
+    pub width: usize,
+}
+
 pub struct Parser<'a> {
     s: &'a str,
     it: iter::Peekable<str::CharIndices<'a>>,
+    /// Number of conditional sections currently open
+    depth: usize,
 }
 
 impl<'a> Parser<'a> {
@@ -17,6 +38,7 @@ impl<'a> Parser<'a> {
         Parser {
             s,
             it: s.char_indices().peekable(),
+            depth: 0,
         }
     }
 
@@ -31,7 +53,40 @@ impl<'a> Parser<'a> {
     }
 
     fn argument(&mut self) -> RawChunk<'a> {
-        RawChunk::Argument(self.name())
+        let name = self.name();
+        if !self.consume(':') {
+            return RawChunk::Argument(name, Spec::default());
+        }
+
+        let mut spec = Spec::default();
+        if self.consume('<') {
+            spec.align = Align::Left;
+        } else if self.consume('>') {
+            spec.align = Align::Right;
+        } else if self.consume('^') {
+            spec.align = Align::Center;
+        }
+
+        while let Some(&(_, ch)) = self.it.peek() {
+            let Some(digit) = ch.to_digit(10) else {
+                break;
+            };
+            spec.width = spec.width.saturating_mul(10).saturating_add(digit as usize);
+            self.it.next();
+        }
+
+        RawChunk::Argument(name, spec)
+    }
+
+    fn conditional(&mut self) -> RawChunk<'a> {
+        let name = self.name();
+        if self.consume('|') {
+            self.depth += 1;
+            RawChunk::Conditional(name)
+        } else {
+            for _ in &mut self.it {}
+            RawChunk::Error("expected '|'")
+        }
     }
 
     fn name(&mut self) -> &'a str {
@@ -57,7 +112,7 @@ impl<'a> Parser<'a> {
     fn text(&mut self, start: usize) -> RawChunk<'a> {
         while let Some(&(pos, ch)) = self.it.peek() {
             match ch {
-                '{' | '}' | ')' => return RawChunk::Text(&self.s[start..pos]),
+                '{' | '}' => return RawChunk::Text(&self.s[start..pos]),
                 _ => {
                     self.it.next();
                 }
@@ -76,6 +131,8 @@ impl<'a> Iterator for Parser<'a> {
                 self.it.next();
                 if self.consume('{') {
                     Some(RawChunk::Text("{"))
+                } else if self.consume('?') {
+                    Some(self.conditional())
                 } else {
                     let chunk = self.argument();
                     if self.consume('}') {
@@ -88,10 +145,69 @@ impl<'a> Iterator for Parser<'a> {
             }
             Some(&(_, '}')) => {
                 self.it.next();
-                Some(RawChunk::Error("unexpected '}'"))
+                if self.consume('}') {
+                    Some(RawChunk::Text("}"))
+                } else if self.depth > 0 {
+                    self.depth -= 1;
+                    Some(RawChunk::End)
+                } else {
+                    Some(RawChunk::Error("unexpected '}'"))
+                }
             }
             Some(&(i, _)) => Some(self.text(i)),
+            None if self.depth > 0 => {
+                self.depth = 0;
+                Some(RawChunk::Error("unterminated conditional, expected '}'"))
+            }
             None => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::format::parse::{Align, Parser, RawChunk, Spec};
+
+    fn parse(s: &str) -> Vec<String> {
+        Parser::new(s)
+            .map(|chunk| match chunk {
+                RawChunk::Text(text) => format!("text {text}"),
+                RawChunk::Argument(name, Spec { align, width }) => {
+                    format!("arg {name} {align:?} {width}")
+                }
+                RawChunk::Conditional(name) => format!("if {name}"),
+                RawChunk::End => "end".to_string(),
+                RawChunk::Error(err) => format!("error {err}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_format_strings() {
+        assert_eq!(
+            parse("{{{n:<30}}}"),
+            vec!["text {", "arg n Left 30", "text }"]
+        );
+        assert_eq!(parse("{s:>8}"), vec![format!("arg s {:?} 8", Align::Right)]);
+        assert_eq!(
+            parse("{n}{?r| ({r})}"),
+            vec![
+                "arg n Left 0",
+                "if r",
+                "text  (",
+                "arg r Left 0",
+                "text )",
+                "end"
+            ]
+        );
+        assert_eq!(parse("}"), vec!["error unexpected '}'"]);
+        assert_eq!(
+            parse("{?r|x"),
+            vec![
+                "if r",
+                "text x",
+                "error unterminated conditional, expected '}'"
+            ]
+        );
+    }
+}
@@ -1,32 +1,185 @@
 use crate::args::RpmArgs;
-use crate::fedora::Pkg;
+use crate::fedora::{Blocker, Pkg};
+use crate::metadata::Lockfile;
+use crate::platform;
+use crate::spec;
 use anyhow::{anyhow, Context, Error};
-use cargo_metadata::{DependencyKind, Metadata, PackageId};
+use cargo_metadata::{DependencyKind, Metadata, PackageId, Source};
+use cargo_platform::Platform;
+use log::debug;
 use petgraph::graph::NodeIndex;
 use petgraph::stable_graph::StableGraph;
 use petgraph::visit::Dfs;
-use std::collections::HashMap;
+use petgraph::EdgeDirection;
+use semver::Version;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub kind: DependencyKind,
+    /// Platforms the dependency is restricted to, `None` if it is unconditional
+    pub targets: Option<Vec<Platform>>,
+}
+
+impl Edge {
+    pub fn on_fedora(&self) -> bool {
+        match &self.targets {
+            Some(targets) => targets.iter().any(platform::matches_fedora),
+            None => true,
+        }
+    }
+}
+
+/// A package selector like `name`, `name@1.2` or `name:1.2.3`
+#[derive(Debug, Clone)]
+pub struct PkgSpec {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl FromStr for PkgSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<PkgSpec, Error> {
+        let (name, version) = match s.split_once(['@', ':']) {
+            Some((name, version)) => (name, Some(version)),
+            None => (s, None),
+        };
+
+        if name.is_empty() {
+            return Err(anyhow!("missing package name in `{}`", s));
+        }
+
+        if let Some(version) = version {
+            // a full version, or a partial one like `1` or `1.2`
+            let valid = Version::parse(version).is_ok() || {
+                let components = version.split('.').collect::<Vec<_>>();
+                components.len() < 3 && components.iter().all(|c| c.parse::<u64>().is_ok())
+            };
+            if !valid {
+                return Err(anyhow!("invalid version `{}` in `{}`", version, s));
+            }
+        }
+
+        Ok(PkgSpec {
+            name: name.to_string(),
+            version: version.map(String::from),
+        })
+    }
+}
+
+impl fmt::Display for PkgSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl PkgSpec {
+    /// Match a package by name and, if given, by a full or partial version
+    pub fn matches(&self, pkg: &Pkg) -> bool {
+        if pkg.name != self.name {
+            return false;
+        }
+
+        let Some(version) = &self.version else {
+            return true;
+        };
+
+        if let Ok(version) = Version::parse(version) {
+            return pkg.version == version;
+        }
+
+        let actual = [pkg.version.major, pkg.version.minor];
+        version
+            .split('.')
+            .zip(actual)
+            .all(|(expected, actual)| expected.parse() == Ok(actual))
+    }
+}
+
+#[derive(Clone)]
 pub struct Graph {
-    pub graph: StableGraph<Pkg, DependencyKind>,
+    pub graph: StableGraph<Pkg, Edge>,
     pub nodes: HashMap<PackageId, NodeIndex>,
     pub root: Option<PackageId>,
+    pub members: Vec<PackageId>,
+    /// Pending specs from `--spec-dir` that can't be built yet
+    pub unsatisfied_specs: Vec<spec::Unsatisfied>,
+}
+
+impl Graph {
+    /// The root package, the workspace members if there is no root, or
+    /// every package nothing depends on if the workspace is unknown
+    pub fn roots(&self) -> Vec<NodeIndex> {
+        if let Some(root) = &self.root {
+            return vec![self.nodes[root]];
+        }
+
+        if !self.members.is_empty() {
+            return self
+                .members
+                .iter()
+                .filter_map(|id| self.nodes.get(id).copied())
+                .collect();
+        }
+
+        self.graph
+            .node_indices()
+            .filter(|idx| {
+                self.graph
+                    .edges_directed(*idx, EdgeDirection::Incoming)
+                    .next()
+                    .is_none()
+            })
+            .collect()
+    }
+
+    /// Whether a package is part of the project itself rather than one of its dependencies
+    pub fn is_project(&self, pkg: &Pkg) -> bool {
+        self.root.as_ref() == Some(&pkg.id) || self.members.contains(&pkg.id)
+    }
+
+    /// Every package the project depends on, without the root and the workspace members
+    pub fn dependencies(&self) -> impl Iterator<Item = &Pkg> {
+        self.graph
+            .node_weights()
+            .filter(|pkg| !self.is_project(pkg))
+    }
 }
 
 pub fn build(args: &RpmArgs, metadata: Metadata) -> Result<Graph, Error> {
     let resolve = metadata
         .resolve
         .context("Unable to resolve dependency information.")?;
+    let included = args.edges().map_err(|err| anyhow!("{}", err))?;
+    let fedora_only = !args.all_targets && args.target.is_none();
 
     let mut graph = Graph {
         graph: StableGraph::new(),
         nodes: HashMap::new(),
-        root: resolve.root,
+        // the workspace members become the roots
+        root: if args.workspace { None } else { resolve.root },
+        members: metadata.workspace_members,
+        unsatisfied_specs: vec![],
     };
 
+    let patched = patched_crates(metadata.workspace_root.join("Cargo.toml").as_std_path());
     for package in metadata.packages {
         let id = package.id.clone();
-        let index = graph.graph.add_node(Pkg::new(package));
+        let mut pkg = Pkg::new(package);
+        // a patch replaces the crates.io release, so the replacement never comes from there
+        let is_patched =
+            patched.contains(&pkg.name) && !pkg.source.as_ref().is_some_and(|s| s.is_crates_io());
+        pkg.blocker = Blocker::detect(&pkg, graph.members.contains(&id), is_patched);
+
+        let index = graph.graph.add_node(pkg);
         graph.nodes.insert(id, index);
     }
 
@@ -36,33 +189,75 @@ pub fn build(args: &RpmArgs, metadata: Metadata) -> Result<Graph, Error> {
         }
 
         let from = graph.nodes[&node.id];
+        graph.graph[from].features = node.features;
+
         for dep in node.deps {
             if dep.dep_kinds.is_empty() {
                 return Err(anyhow!("cargo tree requires cargo 1.41 or newer"));
             }
 
             // https://github.com/rust-lang/cargo/issues/7752
-            let mut kinds = vec![];
+            let mut edges: Vec<Edge> = vec![];
             for kind in dep.dep_kinds {
-                if !kinds.iter().any(|k| *k == kind.kind) {
-                    kinds.push(kind.kind);
+                let edge = match edges.iter_mut().find(|e| e.kind == kind.kind) {
+                    Some(edge) => edge,
+                    None => {
+                        edges.push(Edge {
+                            kind: kind.kind,
+                            targets: Some(vec![]),
+                        });
+                        edges.last_mut().unwrap()
+                    }
+                };
+
+                match (kind.target, &mut edge.targets) {
+                    (Some(target), Some(targets)) => targets.push(target),
+                    (None, targets) => *targets = None,
+                    (Some(_), None) => {}
                 }
             }
 
             let to = graph.nodes[&dep.pkg];
-            for kind in kinds {
-                if args.no_dev_dependencies && kind == DependencyKind::Development {
+            if !included.proc_macro && graph.graph[to].proc_macro {
+                continue;
+            }
+
+            for edge in edges {
+                if !included.includes(edge.kind) {
+                    continue;
+                }
+                // dependencies for other platforms only show up with --all-targets
+                if fedora_only && !edge.on_fedora() {
                     continue;
                 }
 
-                graph.graph.add_edge(from, to, kind);
+                graph.graph.add_edge(from, to, edge);
             }
         }
     }
 
+    // drop excluded crates before pruning, so their own dependencies go as well
+    if !args.exclude.is_empty() {
+        let g = &mut graph.graph;
+        let root = &graph.root;
+        let members = &graph.members;
+        graph.nodes.retain(|id, idx| {
+            if Some(id) != root.as_ref()
+                && !(args.workspace && members.contains(id))
+                && args.exclude.iter().any(|spec| spec.matches(&g[*idx]))
+            {
+                g.remove_node(*idx);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
     // prune nodes not reachable from the root package (directionally)
-    if let Some(root) = &graph.root {
-        let mut dfs = Dfs::new(&graph.graph, graph.nodes[root]);
+    if graph.root.is_some() || args.workspace {
+        let mut dfs = Dfs::empty(&graph.graph);
+        dfs.stack = graph.roots();
         while dfs.next(&graph.graph).is_some() {}
 
         let g = &mut graph.graph;
@@ -76,5 +271,156 @@ pub fn build(args: &RpmArgs, metadata: Metadata) -> Result<Graph, Error> {
         });
     }
 
+    if args.all_targets {
+        platform::mark_patch_out(&mut graph);
+    }
+
     Ok(graph)
 }
+
+/// Names of the crates replaced in the `[patch]` sections of the workspace manifest
+fn patched_crates(manifest_path: &Path) -> HashSet<String> {
+    let manifest = match fs::read_to_string(manifest_path)
+        .map_err(Error::from)
+        .and_then(|content| Ok(toml::from_str::<toml::Table>(&content)?))
+    {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            debug!(
+                "Could not read patches from {}: {:#}",
+                manifest_path.display(),
+                err
+            );
+            return HashSet::new();
+        }
+    };
+
+    let mut patched = HashSet::new();
+    let registries = manifest.get("patch").and_then(|patch| patch.as_table());
+    for crates in registries.into_iter().flat_map(|r| r.values()) {
+        for (name, patch) in crates.as_table().into_iter().flatten() {
+            let name = patch
+                .get("package")
+                .and_then(|package| package.as_str())
+                .unwrap_or(name);
+            patched.insert(name.to_string());
+        }
+    }
+    patched
+}
+
+/// Build a graph from the packages recorded in a Cargo.lock. The lockfile
+/// knows neither dependency kinds nor targets, so every edge is a normal,
+/// unconditional dependency.
+pub fn from_lockfile(args: &RpmArgs, path: &Path, lockfile: Lockfile) -> Result<Graph, Error> {
+    let mut graph = Graph {
+        graph: StableGraph::new(),
+        nodes: HashMap::new(),
+        root: None,
+        members: vec![],
+        unsatisfied_specs: vec![],
+    };
+
+    let manifest_path = path.with_file_name("Cargo.toml");
+    let mut by_name: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+    for package in &lockfile.packages {
+        let version = Version::parse(&package.version)
+            .with_context(|| format!("invalid version of {} in lockfile", package.name))?;
+        let id = PackageId {
+            repr: format!(
+                "{} {} ({})",
+                package.name,
+                package.version,
+                package.source.as_deref().unwrap_or("path")
+            ),
+        };
+        let source = package.source.clone().map(|repr| Source { repr });
+
+        let mut pkg = Pkg::locked(
+            id.clone(),
+            package.name.clone(),
+            version,
+            source,
+            manifest_path.clone(),
+        );
+        // packages without a source are the workspace members
+        pkg.blocker = Blocker::detect(&pkg, true, false);
+        if package.source.is_none() {
+            graph.members.push(id.clone());
+        }
+
+        let index = graph.graph.add_node(pkg);
+        graph.nodes.insert(id, index);
+        by_name.entry(package.name.clone()).or_default().push(index);
+    }
+
+    let indices = graph.graph.node_indices().collect::<Vec<_>>();
+    for (from, package) in indices.into_iter().zip(&lockfile.packages) {
+        for dep in &package.dependencies {
+            // entries are `name`, `name version` or `name version (source)`
+            let mut it = dep.split(' ');
+            let name = it.next().unwrap_or_default();
+            let version = it.next();
+
+            let candidates = by_name.get(name).map(Vec::as_slice).unwrap_or_default();
+            let to = candidates
+                .iter()
+                .find(|idx| version.is_none_or(|v| graph.graph[**idx].version.to_string() == v))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "lockfile dependency `{}` of {} not found",
+                        dep,
+                        package.name
+                    )
+                })?;
+
+            graph.graph.add_edge(
+                from,
+                *to,
+                Edge {
+                    kind: DependencyKind::Normal,
+                    targets: None,
+                },
+            );
+        }
+    }
+
+    if !args.exclude.is_empty() {
+        let g = &mut graph.graph;
+        graph.nodes.retain(|_, idx| {
+            if args.exclude.iter().any(|spec| spec.matches(&g[*idx])) {
+                g.remove_node(*idx);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::PkgSpec;
+
+    #[test]
+    fn parse_pkg_spec() {
+        for spec in [
+            "foo",
+            "foo@1",
+            "foo@1.2",
+            "foo:1.2.3",
+            "foo@1.0.0-rc.1",
+            "foo@1.0.0-alpha.1.2",
+            "foo@1.0.0-rc.1+build.5",
+        ] {
+            let parsed = spec.parse::<PkgSpec>().unwrap();
+            assert_eq!(parsed.name, "foo");
+        }
+
+        for spec in ["@1", "foo@1.2.3.4", "foo@1.x", "foo@1.2.3-"] {
+            assert!(spec.parse::<PkgSpec>().is_err(), "{spec}");
+        }
+    }
+}
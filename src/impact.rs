@@ -0,0 +1,201 @@
+use crate::args::RpmArgs;
+use crate::db::{self, Connection, Requires};
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::format::Pattern;
+use crate::graph::Graph;
+use crate::registry::semver_epoch;
+use crate::spec;
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, HashSet};
+
+/// A Fedora package that would break if the crate was updated in place
+#[derive(Debug, PartialEq)]
+pub struct Dependent {
+    pub source_package: String,
+    pub requirement: String,
+}
+
+/// Convert the flags of a `requires` row into an rpm comparison operator
+fn operator(flags: &str) -> Option<&'static str> {
+    match flags {
+        "EQ" => Some("="),
+        "LT" => Some("<"),
+        "LE" => Some("<="),
+        "GT" => Some(">"),
+        "GE" => Some(">="),
+        _ => None,
+    }
+}
+
+/// Source packages requiring `name` at a version range including `old`, but not `new`.
+/// The subpackages of the crate's own source package are skipped.
+pub fn dependents(
+    requires: &[Requires],
+    name: &str,
+    own_source: &str,
+    old: &Version,
+    new: &Version,
+) -> Vec<Dependent> {
+    // rust2rpm used to split a range into one row per comparison, so join the rows per package
+    let mut by_package: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for row in requires {
+        if row.source_package == own_source {
+            continue;
+        }
+        let text = match (row.flags.as_deref().and_then(operator), &row.version) {
+            (Some(op), Some(version)) => format!("{} {} {}", row.name, op, version),
+            _ => row.name.clone(),
+        };
+        by_package
+            .entry((&row.source_package, &row.package))
+            .or_default()
+            .push(text);
+    }
+
+    let krate = format!("crate({name})");
+    let feature = format!("crate({name}/");
+    let mut dependents = vec![];
+    for ((source_package, _), texts) in by_package {
+        for requirement in spec::parse_requirements(&texts.join(" ")) {
+            if requirement.name != krate && !requirement.name.starts_with(&feature) {
+                continue;
+            }
+            if requirement.req == VersionReq::STAR
+                || !requirement.req.matches(old)
+                || requirement.req.matches(new)
+            {
+                continue;
+            }
+
+            let dependent = Dependent {
+                source_package: source_package.to_string(),
+                requirement: requirement.to_string(),
+            };
+            if !dependents.contains(&dependent) {
+                dependents.push(dependent);
+            }
+        }
+    }
+    dependents
+}
+
+/// Name of a compat package for the version Fedora has now, e.g. `rust-foo0.3`
+fn compat_name(source_package: &str, version: &Version) -> String {
+    let epoch = match semver_epoch(version) {
+        (0, 0, patch) => format!("0.0.{patch}"),
+        (0, minor, _) => format!("0.{minor}"),
+        (major, _, _) => format!("{major}"),
+    };
+    format!("{source_package}{epoch}")
+}
+
+pub fn print(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
+    let format = Pattern::new(&args.format)?;
+
+    let mut seen = HashSet::new();
+    let mut outdated = graph
+        .dependencies()
+        .filter(|pkg| pkg.packaging_status() == PackagingProgress::NeedsUpdate)
+        .filter(|pkg| seen.insert((&pkg.name, &pkg.version)))
+        .collect::<Vec<&Pkg>>();
+    if outdated.is_empty() {
+        println!("No outdated crates");
+        return Ok(());
+    }
+    outdated.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    let mut db = Connection::new(&outdated[0].rpmrelease)?;
+    for (i, pkg) in outdated.iter().enumerate() {
+        if i != 0 {
+            println!();
+        }
+        println!(" {} {}", pkg.packaging_status(), format.display(pkg));
+
+        let Some(rpm) = &pkg.rpminfo else {
+            continue;
+        };
+        let Ok(old) = db::parse_rpm_version(&rpm.version) else {
+            println!("     can't compare the Fedora version {}", rpm.version);
+            continue;
+        };
+        let source_package = if rpm.source_package.is_empty() {
+            format!("rust-{}", pkg.name)
+        } else {
+            rpm.source_package.clone()
+        };
+
+        let requires = db.requires(&pkg.name)?;
+        let dependents = dependents(&requires, &pkg.name, &source_package, &old, &pkg.version);
+        if dependents.is_empty() {
+            println!(
+                "     nothing else in {} requires {} {}",
+                pkg.rpmrelease, pkg.name, old
+            );
+            println!("     recommendation: update {source_package} in place");
+            continue;
+        }
+
+        println!("     still requiring {} {}:", pkg.name, old);
+        for dependent in &dependents {
+            println!(
+                "       {} ({})",
+                dependent.source_package, dependent.requirement
+            );
+        }
+        println!(
+            "     recommendation: add a compat package {} for them, or port them to {}",
+            compat_name(&source_package, &old),
+            pkg.version
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Requires;
+    use crate::impact::{compat_name, dependents};
+    use semver::Version;
+
+    fn row(source: &str, name: &str, flags: Option<&str>, version: Option<&str>) -> Requires {
+        Requires {
+            package: format!("{source}-devel"),
+            source_package: source.to_string(),
+            name: name.to_string(),
+            flags: flags.map(String::from),
+            version: version.map(String::from),
+        }
+    }
+
+    #[test]
+    fn find_dependents() {
+        let requires = [
+            // the crate's own feature subpackages
+            row("rust-foo", "crate(foo)", Some("EQ"), Some("0.3.5")),
+            row(
+                "rust-bar",
+                "(crate(foo/default) >= 0.3.0 with crate(foo/default) < 0.4.0~)",
+                None,
+                None,
+            ),
+            // a range split into two rows
+            row("rust-baz", "crate(foo)", Some("GE"), Some("0.3.1")),
+            row("rust-baz", "crate(foo)", Some("LT"), Some("0.4.0~")),
+            row("rust-qux", "crate(foo/std)", Some("GE"), Some("0.3.0")),
+            row("rust-other", "crate(foobar)", Some("GE"), Some("0.3.0")),
+        ];
+        let old = Version::parse("0.3.5").unwrap();
+        let new = Version::parse("0.4.1").unwrap();
+
+        let names = dependents(&requires, "foo", "rust-foo", &old, &new)
+            .into_iter()
+            .map(|d| d.source_package)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["rust-bar", "rust-baz"]);
+
+        assert_eq!(compat_name("rust-foo", &old), "rust-foo0.3");
+        assert_eq!(compat_name("rust-foo", &new), "rust-foo0.4");
+    }
+}
@@ -0,0 +1,397 @@
+use crate::db::{self, PkgInfo, PkgStatus};
+use crate::errors::*;
+use crate::fedora::Pkg;
+use crate::graph::Graph;
+use semver::Version;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+const LEAD_SIZE: usize = 96;
+const LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8];
+const PREAMBLE_SIZE: usize = 16;
+/// Upper bound for the index and store of a header, rpm itself refuses anything larger
+const MAX_HEADER_SIZE: usize = 256 << 20;
+
+const TAG_NAME: u32 = 1000;
+const TAG_VERSION: u32 = 1001;
+const TAG_RELEASE: u32 = 1002;
+const TAG_EPOCH: u32 = 1003;
+const TAG_ARCH: u32 = 1022;
+const TAG_SOURCERPM: u32 = 1044;
+const TAG_PROVIDENAME: u32 = 1047;
+const TAG_PROVIDEVERSION: u32 = 1113;
+
+const TYPE_INT32: u32 = 4;
+const TYPE_STRING: u32 = 6;
+const TYPE_STRING_ARRAY: u32 = 8;
+
+/// The parts of a binary RPM header needed to tell which crates it provides
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalRpm {
+    pub name: String,
+    pub epoch: Option<u32>,
+    pub version: String,
+    pub release: String,
+    pub arch: String,
+    pub sourcerpm: Option<String>,
+    /// `crate(foo)` and friends with their version, if any
+    pub provides: Vec<(String, Option<String>)>,
+}
+
+struct Entry {
+    tag: u32,
+    kind: u32,
+    offset: usize,
+    count: usize,
+}
+
+struct Header<'a> {
+    entries: Vec<Entry>,
+    store: &'a [u8],
+}
+
+fn be32(data: &[u8], pos: usize) -> Result<u32, Error> {
+    let bytes = data
+        .get(pos..pos + 4)
+        .context("unexpected end of rpm header")?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+impl<'a> Header<'a> {
+    /// Parse a header structure as returned by `read_structure`
+    fn parse(data: &'a [u8]) -> Result<Header<'a>, Error> {
+        let count = be32(data, 8)? as usize;
+        let size = be32(data, 12)? as usize;
+
+        let index = PREAMBLE_SIZE;
+        let store_start = index + count * 16;
+        let store = data
+            .get(store_start..store_start + size)
+            .context("truncated rpm header")?;
+
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let at = index + i * 16;
+            entries.push(Entry {
+                tag: be32(data, at)?,
+                kind: be32(data, at + 4)?,
+                offset: be32(data, at + 8)? as usize,
+                count: be32(data, at + 12)? as usize,
+            });
+        }
+
+        Ok(Header { entries, store })
+    }
+
+    fn entry(&self, tag: u32, kind: u32) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.tag == tag && entry.kind == kind)
+    }
+
+    fn strings(&self, tag: u32) -> Vec<String> {
+        let Some(entry) = self
+            .entry(tag, TYPE_STRING_ARRAY)
+            .or_else(|| self.entry(tag, TYPE_STRING))
+        else {
+            return vec![];
+        };
+
+        self.store
+            .get(entry.offset..)
+            .unwrap_or_default()
+            .split(|b| *b == 0)
+            .take(entry.count)
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect()
+    }
+
+    fn string(&self, tag: u32) -> Option<String> {
+        self.strings(tag).into_iter().next()
+    }
+
+    fn int32(&self, tag: u32) -> Option<u32> {
+        let entry = self.entry(tag, TYPE_INT32)?;
+        be32(self.store, entry.offset).ok()
+    }
+}
+
+/// Read a header structure, its preamble followed by the index and the store, and nothing more
+fn read_structure(reader: &mut impl Read) -> Result<Vec<u8>, Error> {
+    let mut data = vec![0; PREAMBLE_SIZE];
+    reader
+        .read_exact(&mut data)
+        .context("unexpected end of rpm header")?;
+    if !data.starts_with(HEADER_MAGIC) {
+        bail!("invalid rpm header magic");
+    }
+    let count = be32(&data, 8)? as usize;
+    let size = be32(&data, 12)? as usize;
+    let len = count * 16 + size;
+    if len > MAX_HEADER_SIZE {
+        bail!("rpm header too large");
+    }
+
+    reader.take(len as u64).read_to_end(&mut data)?;
+    if data.len() != PREAMBLE_SIZE + len {
+        bail!("truncated rpm header");
+    }
+    Ok(data)
+}
+
+/// Read the lead, skip the signature and parse the main header of an RPM file.
+/// The payload isn't needed, so reading stops right before it.
+pub fn read_header(reader: &mut impl Read) -> Result<LocalRpm, Error> {
+    let mut lead = [0; LEAD_SIZE];
+    reader.read_exact(&mut lead).context("not an rpm file")?;
+    if !lead.starts_with(LEAD_MAGIC) {
+        bail!("not an rpm file");
+    }
+
+    let signature = read_structure(reader).context("invalid signature header")?;
+    // the signature is padded to a multiple of 8 bytes
+    let mut padding = vec![0; signature.len().next_multiple_of(8) - signature.len()];
+    reader
+        .read_exact(&mut padding)
+        .context("unexpected end of rpm header")?;
+    let data = read_structure(reader)?;
+    let header = Header::parse(&data)?;
+
+    let names = header.strings(TAG_PROVIDENAME);
+    let versions = header.strings(TAG_PROVIDEVERSION);
+    let provides = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let version = versions.get(i).filter(|v| !v.is_empty()).cloned();
+            (name, version)
+        })
+        .collect();
+
+    Ok(LocalRpm {
+        name: header.string(TAG_NAME).context("rpm without a name")?,
+        epoch: header.int32(TAG_EPOCH),
+        version: header.string(TAG_VERSION).unwrap_or_default(),
+        release: header.string(TAG_RELEASE).unwrap_or_default(),
+        arch: header.string(TAG_ARCH).unwrap_or_default(),
+        sourcerpm: header.string(TAG_SOURCERPM),
+        provides,
+    })
+}
+
+/// Binary RPMs from a local build, e.g. the results of `fedpkg mockbuild`
+pub struct LocalRepo {
+    rpms: Vec<LocalRpm>,
+}
+
+impl LocalRepo {
+    pub fn load(dir: &Path) -> Result<LocalRepo, Error> {
+        let mut rpms = vec![];
+        let entries =
+            fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !name.ends_with(".rpm") || name.ends_with(".src.rpm") {
+                continue;
+            }
+
+            let file =
+                File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+            match read_header(&mut BufReader::new(file)) {
+                Ok(rpm) => {
+                    debug!("Read local rpm {}: {:?}", path.display(), rpm);
+                    rpms.push(rpm);
+                }
+                Err(err) => warn!("Skipping {}: {:#}", path.display(), err),
+            }
+        }
+        info!("Read {} local rpms from {}", rpms.len(), dir.display());
+
+        Ok(LocalRepo { rpms })
+    }
+
+    /// Find the best local rpm providing `crate(name)` for the required version
+    pub fn search(&self, name: &str, version: &Version) -> Result<PkgInfo, Error> {
+        let mut info = PkgInfo {
+            status: PkgStatus::NotFound,
+            version: String::new(),
+            package: String::new(),
+            evr: String::new(),
+            arch: String::new(),
+            source_package: String::new(),
+        };
+        let (exact, compatible) = db::requirements(version)?;
+        let provide = format!("crate({name})");
+
+        for rpm in &self.rpms {
+            let provided = rpm
+                .provides
+                .iter()
+                .find(|(p, _)| *p == provide)
+                .map(|(_, version)| version.clone().unwrap_or_else(|| rpm.version.clone()));
+            let Some(provided) = provided else {
+                continue;
+            };
+            if db::parse_rpm_version(&provided).is_err() {
+                warn!("Ignoring {} from {}: invalid version", provide, rpm.name);
+                continue;
+            }
+
+            let status = if db::is_compatible(&provided, &exact)? {
+                PkgStatus::Found
+            } else if db::is_compatible(&provided, &compatible)? {
+                PkgStatus::Compatible
+            } else {
+                PkgStatus::Outdated
+            };
+            if rank(&status) <= rank(&info.status) {
+                continue;
+            }
+
+            info.status = status;
+            info.version = provided;
+            info.package = rpm.name.clone();
+            info.arch = rpm.arch.clone();
+            info.evr = match rpm.epoch {
+                None | Some(0) => format!("{}-{}", rpm.version, rpm.release),
+                Some(epoch) => format!("{}:{}-{}", epoch, rpm.version, rpm.release),
+            };
+            info.source_package = rpm
+                .sourcerpm
+                .as_deref()
+                .and_then(db::source_package_name)
+                .unwrap_or_default();
+        }
+
+        Ok(info)
+    }
+}
+
+fn rank(status: &PkgStatus) -> u8 {
+    match status {
+        PkgStatus::NotFound => 0,
+        PkgStatus::Outdated => 1,
+        PkgStatus::Compatible => 2,
+        PkgStatus::Found => 3,
+    }
+}
+
+/// How the repository knows the package, to compare it against the local rpms
+fn repo_status(pkg: &Pkg) -> PkgStatus {
+    match &pkg.rpminfo {
+        Some(rpm) if rpm.exact_match => PkgStatus::Found,
+        Some(rpm) if rpm.in_rawhide && rpm.compatible => PkgStatus::Compatible,
+        Some(rpm) if rpm.outdated => PkgStatus::Outdated,
+        _ => PkgStatus::NotFound,
+    }
+}
+
+/// Replace the repository results with local rpms wherever those are a better match
+pub fn populate(graph: &mut Graph, repo: &LocalRepo) -> Result<(), Error> {
+    for pkg in graph.graph.node_weights_mut() {
+        let info = repo.search(&pkg.name, &pkg.version)?;
+        if rank(&info.status) <= rank(&repo_status(pkg)) {
+            continue;
+        }
+        let Some(rpm) = &mut pkg.rpminfo else {
+            continue;
+        };
+
+        rpm.in_rawhide = true;
+        rpm.exact_match = info.status == PkgStatus::Found;
+        rpm.compatible = info.status == PkgStatus::Compatible;
+        rpm.outdated = info.status == PkgStatus::Outdated;
+        rpm.version = info.version;
+        rpm.package = info.package;
+        rpm.evr = info.evr;
+        rpm.arch = info.arch;
+        rpm.source_package = info.source_package;
+        rpm.local = true;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::PkgStatus;
+    use crate::local::{read_header, LocalRepo};
+    use semver::Version;
+
+    /// Serialize a header structure from (tag, type, count, data) entries
+    fn header(entries: &[(u32, u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut index = vec![];
+        let mut store = vec![];
+        for (tag, kind, count, data) in entries {
+            if *kind == 4 {
+                store.resize(store.len().next_multiple_of(4), 0);
+            }
+            for value in [*tag, *kind, store.len() as u32, *count] {
+                index.extend(value.to_be_bytes());
+            }
+            store.extend(data);
+        }
+
+        let mut out = vec![0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+        out.extend((entries.len() as u32).to_be_bytes());
+        out.extend((store.len() as u32).to_be_bytes());
+        out.extend(index);
+        out.extend(store);
+        out
+    }
+
+    fn strings(values: &[&str]) -> Vec<u8> {
+        values.iter().flat_map(|s| s.bytes().chain([0])).collect()
+    }
+
+    #[test]
+    fn read_rpm_header() {
+        let mut rpm = vec![0xed, 0xab, 0xee, 0xdb];
+        rpm.resize(96, 0);
+        rpm.extend(header(&[(1000, 7, 3, vec![1, 2, 3])]));
+        rpm.resize(rpm.len().next_multiple_of(8), 0);
+        rpm.extend(header(&[
+            (1000, 6, 1, strings(&["rust-anyhow+default-devel"])),
+            (1001, 6, 1, strings(&["1.0.98"])),
+            (1002, 6, 1, strings(&["1.fc43"])),
+            (1003, 4, 1, 1u32.to_be_bytes().to_vec()),
+            (1022, 6, 1, strings(&["noarch"])),
+            (1044, 6, 1, strings(&["rust-anyhow-1.0.98-1.fc43.src.rpm"])),
+            (
+                1047,
+                8,
+                2,
+                strings(&["crate(anyhow/default)", "crate(anyhow)"]),
+            ),
+            (1113, 8, 2, strings(&["1.0.98", "1.0.98"])),
+        ]));
+
+        // the payload isn't read
+        rpm.extend(b"payload");
+        assert!(read_header(&mut &rpm[..rpm.len() - 8]).is_err());
+        let header = read_header(&mut rpm.as_slice()).unwrap();
+        assert_eq!(header.name, "rust-anyhow+default-devel");
+        assert_eq!(header.epoch, Some(1));
+        assert_eq!(header.provides[1].0, "crate(anyhow)");
+
+        let repo = LocalRepo { rpms: vec![header] };
+        let info = repo
+            .search("anyhow", &Version::parse("1.0.90").unwrap())
+            .unwrap();
+        assert_eq!(info.status, PkgStatus::Found);
+        assert_eq!(info.evr, "1:1.0.98-1.fc43");
+        assert_eq!(info.arch, "noarch");
+        assert_eq!(info.source_package, "rust-anyhow");
+        let info = repo
+            .search("anyhow", &Version::parse("1.0.99").unwrap())
+            .unwrap();
+        assert_eq!(info.status, PkgStatus::Compatible);
+        let info = repo
+            .search("anyhow-ext", &Version::parse("1.0.0").unwrap())
+            .unwrap();
+        assert_eq!(info.status, PkgStatus::NotFound);
+    }
+}
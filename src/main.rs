@@ -2,32 +2,89 @@ use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
+use std::process::ExitCode;
 
 use args::RpmArgs;
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, FromArgMatches};
 
-use crate::args::{Opts, RpmStatus};
+use crate::args::{Command, Opts, OutputFormat, RpmStatus};
 use crate::errors::*;
+use crate::policy::Policy;
 
 mod args;
+mod config;
 mod db;
+mod diff;
 mod errors;
 mod fedora;
 mod format;
 mod graph;
+mod impact;
+mod local;
 mod metadata;
+mod output;
+mod platform;
+mod policy;
+mod registry;
+mod spec;
+mod style;
+mod summary;
 mod tree;
+mod why;
 
-fn main() -> Result<(), Error> {
+/// Exit code when the packaging policy isn't met
+const EXIT_POLICY: u8 = 1;
+/// Exit code when the check itself failed
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
     env_logger::init();
 
-    let args = match RpmStatus::parse().cmd {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_POLICY),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Returns whether the packaging policy is met
+fn run() -> Result<bool, Error> {
+    let matches = RpmStatus::command().get_matches();
+    let mut args = match RpmStatus::from_arg_matches(&matches)?.cmd {
         Opts::Tree(args) => args,
         Opts::Man => {
-            return generate_manpage();
+            generate_manpage()?;
+            return Ok(true);
         }
     };
 
+    if let Some(matches) = matches.subcommand_matches("rpmstatus") {
+        info!("Reading config");
+        config::load(&args)?.apply(&mut args, matches)?;
+    }
+
+    style::init(&args)?;
+
+    if let Some(Command::Diff(diff)) = &args.command {
+        diff::run(&args, diff)?;
+        return Ok(true);
+    }
+
+    if matches!(args.command, Some(Command::Impact)) && !matches!(args.output, OutputFormat::Tree) {
+        bail!("impact only prints text, --output isn't supported");
+    }
+
+    let policy = match &args.policy {
+        Some(path) => Some(Policy::load(path)?),
+        None if Path::new(policy::DEFAULT_PATH).exists() => {
+            Some(Policy::load(Path::new(policy::DEFAULT_PATH))?)
+        }
+        None => None,
+    };
+
     info!("Reading metadata");
     let metadata = metadata::get(&args)?;
 
@@ -35,11 +92,38 @@ fn main() -> Result<(), Error> {
     let mut graph = graph::build(&args, metadata)?;
 
     info!("Populating with packaging data");
-    fedora::populate(&mut graph, args.rpm_release.clone())?;
-    info!("Printing graph");
-    tree::print(&args, &graph)?;
+    fedora::populate(&args, &mut graph, args.rpm_release.first().cloned())?;
 
-    Ok(())
+    if args.upstream || args.needs_bump {
+        info!("Reading upstream versions from the local registry index");
+        registry::populate(&mut graph)?;
+    }
+
+    match &args.command {
+        Some(Command::Why(why)) => {
+            info!("Printing dependency paths");
+            why::print(&args, why, &graph)?;
+        }
+        Some(Command::Summary) => {
+            info!("Printing summary");
+            summary::print(&args, &graph)?;
+        }
+        Some(Command::Impact) => {
+            info!("Printing update impact");
+            impact::print(&args, &graph)?;
+        }
+        _ => {
+            info!("Printing graph");
+            output::print(&args, &graph)?;
+
+            if matches!(args.output, OutputFormat::Tree) && !args.quiet {
+                println!();
+                summary::print(&args, &graph)?;
+            }
+        }
+    }
+
+    Ok(policy.is_none_or(|policy| policy.report(&graph)))
 }
 
 fn generate_manpage() -> anyhow::Result<()> {
@@ -4,9 +4,10 @@ use cargo_metadata::Metadata;
 use flate2::read::GzDecoder;
 use log::{debug, info, trace};
 use rand::distr::{Alphanumeric, SampleString};
+use serde::Deserialize;
 use std::ffi::OsString;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::{env, fs};
 use tar::Archive;
@@ -31,21 +32,15 @@ pub fn get(args: &RpmArgs) -> Result<Metadata> {
         command.arg("--no-default-features");
     }
 
-    if !args.all_targets {
-        command.arg("--filter-platform");
-        match &args.target {
-            Some(target) => {
-                command.arg(target);
-            }
-            None => {
-                let target = default_target()?;
-                command.arg(target);
-            }
-        }
+    // without a target, the graph keeps the dependencies of every Fedora architecture
+    if !args.all_targets
+        && let Some(target) = &args.target
+    {
+        command.arg("--filter-platform").arg(target);
     }
 
     if let Some(path) = &args.crate_path {
-        let extracted_path = extract_crate_cargo_toml(path)?;
+        let extracted_path = extract_crate_manifest(path)?;
         debug!(
             "Using extracted Cargo.toml at {}",
             &extracted_path.display()
@@ -82,18 +77,25 @@ pub fn get(args: &RpmArgs) -> Result<Metadata> {
     serde_json::from_str(&output).context("error parsing cargo metadata output")
 }
 
-fn default_target() -> Result<String, Error> {
-    let rustc = env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
-    let output = output(Command::new(rustc).arg("-Vv"), "rustc")?;
+/// Manifest of the package the current directory belongs to, `None` outside of any package
+pub fn locate_project() -> Result<Option<PathBuf>> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
 
-    for line in output.lines() {
-        let prefix = "host: ";
-        if let Some(text) = line.strip_prefix(prefix) {
-            return Ok(text.trim().to_string());
-        }
+    // the error is reported by `cargo metadata` later on
+    let output = Command::new(cargo)
+        .arg("locate-project")
+        .arg("--message-format")
+        .arg("plain")
+        .stderr(Stdio::null())
+        .output()
+        .context("error running cargo locate-project")?;
+    if !output.status.success() {
+        return Ok(None);
     }
 
-    Err(anyhow!("host missing from rustc output"))
+    let path =
+        String::from_utf8(output.stdout).context("error parsing cargo locate-project output")?;
+    Ok(Some(PathBuf::from(path.trim_end())))
 }
 
 fn output(command: &mut Command, job: &str) -> Result<String, Error> {
@@ -109,7 +111,7 @@ fn output(command: &mut Command, job: &str) -> Result<String, Error> {
     String::from_utf8(output.stdout).with_context(|| format!("error parsing {job} output"))
 }
 
-fn extract_crate_cargo_toml(crate_path: &PathBuf) -> Result<PathBuf> {
+fn extract_crate_manifest(crate_path: &PathBuf) -> Result<PathBuf> {
     let tmp_dir = Alphanumeric.sample_string(&mut rand::rng(), 16);
     let tmp_path = env::temp_dir().join(tmp_dir);
 
@@ -122,21 +124,52 @@ fn extract_crate_cargo_toml(crate_path: &PathBuf) -> Result<PathBuf> {
     let tar_gz = File::open(crate_path)?;
     let tar = GzDecoder::new(tar_gz);
     let mut archive = Archive::new(tar);
-    let manifest = archive.entries()?.filter_map(|e| e.ok()).find(|x| {
-        trace!("found entry {:?}", x.path());
-        x.path().unwrap_or_default().ends_with("Cargo.toml")
-    });
-
-    match manifest {
-        Some(mut entry) => {
-            let written = entry.unpack_in(&tmp_path)?;
 
-            if !written {
-                bail!("could not extract manifest from crate file");
-            }
+    // a Cargo.lock shipped next to the manifest pins the versions cargo resolves to
+    let mut manifest = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        trace!("found entry {:?}", path);
+
+        let in_root = path.components().count() == 2;
+        let Some(name) = path.file_name().filter(|_| in_root) else {
+            continue;
+        };
+        if name != "Cargo.toml" && name != "Cargo.lock" {
+            continue;
+        }
 
-            return Ok(tmp_path.join(entry.path()?));
+        if !entry.unpack_in(&tmp_path)? {
+            bail!("could not extract {} from crate file", path.display());
+        }
+        if name == "Cargo.toml" {
+            manifest = Some(tmp_path.join(path));
+        } else {
+            debug!("Using the Cargo.lock shipped in the crate");
         }
-        None => bail!("could not find manifest file in crate"),
     }
+
+    manifest.context("could not find manifest file in crate")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+pub fn read_lockfile(path: &Path) -> Result<Lockfile> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("could not read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("error parsing {}", path.display()))
 }
@@ -0,0 +1,325 @@
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg, RpmInfo};
+use crate::graph::Graph;
+use cargo_metadata::DependencyKind;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+const SPEC_VERSION: &str = "1.5";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom<'a> {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: Metadata<'a>,
+    components: Vec<Component<'a>>,
+    dependencies: Vec<Dependency<'a>>,
+}
+
+#[derive(Serialize)]
+struct Metadata<'a> {
+    tools: Tools,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<Component<'a>>,
+}
+
+#[derive(Serialize)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Component<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: &'a str,
+    name: &'a str,
+    version: String,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<License<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<ExternalReference>,
+    properties: Vec<Property>,
+}
+
+#[derive(Serialize)]
+struct License<'a> {
+    expression: &'a str,
+}
+
+#[derive(Serialize)]
+struct ExternalReference {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Property {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Dependency<'a> {
+    #[serde(rename = "ref")]
+    bom_ref: &'a str,
+    depends_on: BTreeSet<&'a str>,
+}
+
+/// Whether the crate has to be vendored into the RPM instead of coming from a Fedora package
+fn bundled(pkg: &Pkg) -> bool {
+    matches!(
+        pkg.packaging_status(),
+        PackagingProgress::Missing | PackagingProgress::NeedsUpdate | PackagingProgress::Blocker
+    )
+}
+
+/// Why a pending crate is expected to be packaged: the `--assume-packaged` note or the spec file
+fn pending(pkg: &Pkg) -> Option<String> {
+    let rpm = pkg.rpminfo.as_ref()?;
+    match pkg.packaging_status() {
+        PackagingProgress::Pending => Some(rpm.note.clone().unwrap_or_else(|| "true".to_string())),
+        PackagingProgress::SpecPending => rpm.spec.as_ref().map(|spec| spec.display().to_string()),
+        _ => None,
+    }
+}
+
+/// Package URL of the providing RPM, the epoch and arch are qualifiers rather than part of the version
+fn rpm_purl(rpm: &RpmInfo) -> String {
+    let (epoch, version) = match rpm.evr.split_once(':') {
+        Some((epoch, version)) => (Some(epoch), version),
+        None => (None, rpm.evr.as_str()),
+    };
+    let mut purl = format!(
+        "pkg:rpm/fedora/{}@{}",
+        rpm.package,
+        version.replace('+', "%2B").replace('^', "%5E")
+    );
+
+    // qualifiers are sorted by key
+    let mut qualifiers = vec![];
+    if !rpm.arch.is_empty() {
+        qualifiers.push(format!("arch={}", rpm.arch));
+    }
+    if let Some(epoch) = epoch {
+        qualifiers.push(format!("epoch={epoch}"));
+    }
+    if !qualifiers.is_empty() {
+        purl.push('?');
+        purl.push_str(&qualifiers.join("&"));
+    }
+    purl
+}
+
+/// Crates ending up in the shipped RPM: reachable from the project through normal dependencies,
+/// and not only needed on other platforms
+fn shipped(graph: &Graph) -> HashSet<NodeIndex> {
+    let mut shipped = HashSet::new();
+    let mut stack = graph.roots();
+    while let Some(idx) = stack.pop() {
+        if graph.graph[idx].patch_out || !shipped.insert(idx) {
+            continue;
+        }
+        stack.extend(
+            graph
+                .graph
+                .edges(idx)
+                .filter(|edge| edge.weight().kind == DependencyKind::Normal)
+                .map(|edge| edge.target()),
+        );
+    }
+    shipped
+}
+
+impl<'a> Component<'a> {
+    /// The packaging properties are left out for the `project`'s own packages
+    fn new(pkg: &'a Pkg, kind: &'static str, project: bool) -> Component<'a> {
+        let mut external_references = vec![];
+        if let Some(repository) = &pkg.repository {
+            external_references.push(ExternalReference {
+                kind: "vcs",
+                url: repository.clone(),
+                comment: None,
+            });
+        }
+
+        let mut properties = vec![Property {
+            name: "cargo-rpmstatus:status",
+            value: pkg.packaging_status().name().to_string(),
+        }];
+
+        if project {
+            // the project itself is what gets packaged, it is neither bundled nor provided
+        } else if bundled(pkg) {
+            properties.push(Property {
+                name: "cargo-rpmstatus:bundled",
+                value: "true".to_string(),
+            });
+        } else if let Some(pending) = pending(pkg) {
+            // not provided by an RPM yet, but expected to be instead of getting vendored
+            properties.push(Property {
+                name: "cargo-rpmstatus:pending",
+                value: pending,
+            });
+            properties.push(Property {
+                name: "cargo-rpmstatus:bundled",
+                value: "false".to_string(),
+            });
+        } else if let Some(rpm) = &pkg.rpminfo
+            && rpm.in_rawhide
+            && !rpm.package.is_empty()
+        {
+            external_references.push(ExternalReference {
+                kind: "distribution",
+                url: rpm_purl(rpm),
+                comment: Some(format!(
+                    "Provided by {} {} in {}",
+                    rpm.package,
+                    rpm.evr,
+                    pkg.repo()
+                )),
+            });
+            properties.push(Property {
+                name: "cargo-rpmstatus:bundled",
+                value: "false".to_string(),
+            });
+        }
+
+        Component {
+            kind,
+            bom_ref: &pkg.id.repr,
+            name: &pkg.name,
+            version: pkg.version.to_string(),
+            purl: format!("pkg:cargo/{}@{}", pkg.name, pkg.version),
+            licenses: pkg
+                .license
+                .as_deref()
+                .map(|expression| License { expression })
+                .into_iter()
+                .collect(),
+            external_references,
+            properties,
+        }
+    }
+}
+
+pub fn print(graph: &Graph) -> Result<(), Error> {
+    let shipped = shipped(graph);
+
+    let root = graph.root.as_ref().map(|id| &graph.graph[graph.nodes[id]]);
+    let members = graph
+        .roots()
+        .into_iter()
+        .map(|idx| &graph.graph[idx].id)
+        .collect::<BTreeSet<_>>();
+
+    let mut components = shipped
+        .iter()
+        .map(|idx| &graph.graph[*idx])
+        .filter(|pkg| root.is_none_or(|root| root.id != pkg.id))
+        .map(|pkg| {
+            let kind = if members.contains(&pkg.id) {
+                "application"
+            } else {
+                "library"
+            };
+            Component::new(pkg, kind, graph.is_project(pkg))
+        })
+        .collect::<Vec<_>>();
+    components.sort_by_key(|component| component.bom_ref);
+
+    let mut dependencies = BTreeMap::new();
+    for idx in &shipped {
+        let depends_on = graph
+            .graph
+            .edges(*idx)
+            .filter(|edge| {
+                edge.weight().kind == DependencyKind::Normal && shipped.contains(&edge.target())
+            })
+            .map(|edge| graph.graph[edge.target()].id.repr.as_str())
+            .collect();
+        dependencies.insert(graph.graph[*idx].id.repr.as_str(), depends_on);
+    }
+
+    let bom = Bom {
+        bom_format: "CycloneDX",
+        spec_version: SPEC_VERSION,
+        version: 1,
+        metadata: Metadata {
+            tools: Tools {
+                components: vec![Tool {
+                    kind: "application",
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            component: root.map(|root| Component::new(root, "application", true)),
+        },
+        components,
+        dependencies: dependencies
+            .into_iter()
+            .map(|(bom_ref, depends_on)| Dependency {
+                bom_ref,
+                depends_on,
+            })
+            .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&bom)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fedora::RpmInfo;
+    use crate::output::cyclonedx::rpm_purl;
+
+    fn rpm(evr: &str, arch: &str) -> RpmInfo {
+        RpmInfo {
+            in_rawhide: true,
+            outdated: false,
+            compatible: true,
+            exact_match: true,
+            version: "1.0.0".to_string(),
+            package: "rust-foo-devel".to_string(),
+            evr: evr.to_string(),
+            arch: arch.to_string(),
+            source_package: String::new(),
+            pending: false,
+            note: None,
+            local: false,
+            spec: None,
+        }
+    }
+
+    #[test]
+    fn purl_qualifiers() {
+        assert_eq!(
+            rpm_purl(&rpm("1.0.0-1.fc42", "")),
+            "pkg:rpm/fedora/rust-foo-devel@1.0.0-1.fc42"
+        );
+        assert_eq!(
+            rpm_purl(&rpm("2:1.0.0^git-1.fc42", "noarch")),
+            "pkg:rpm/fedora/rust-foo-devel@1.0.0%5Egit-1.fc42?arch=noarch&epoch=2"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::graph::Graph;
+use cargo_metadata::DependencyKind;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use std::collections::HashSet;
+
+fn needs_work(pkg: &Pkg) -> bool {
+    matches!(
+        pkg.packaging_status(),
+        PackagingProgress::Missing | PackagingProgress::NeedsUpdate | PackagingProgress::Blocker
+    )
+}
+
+fn fill_color(status: PackagingProgress) -> &'static str {
+    match status {
+        PackagingProgress::Available => "palegreen",
+        PackagingProgress::Compatible => "darkseagreen1",
+        PackagingProgress::Pending | PackagingProgress::SpecPending => "lightblue",
+        PackagingProgress::NeedsUpdate => "gold",
+        PackagingProgress::Missing => "lightcoral",
+        PackagingProgress::PatchOut => "lightgrey",
+        PackagingProgress::Blocker => "firebrick",
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn label(pkg: &Pkg) -> String {
+    let mut label = format!("{}\\nv{}", escape(&pkg.name), pkg.version);
+    if let Some(rpm) = &pkg.rpminfo {
+        if rpm.in_rawhide && rpm.compatible {
+            label.push_str(&format!("\\n{} in {}", escape(&rpm.version), pkg.repo()));
+        } else if rpm.outdated {
+            label.push_str(&format!("\\noutdated, {}", escape(&rpm.version)));
+        }
+    }
+    if pkg.blocker.is_some() {
+        label.push_str("\\nblocker");
+    }
+    label
+}
+
+/// Print the graph in Graphviz format. With `unpackaged` only the crates that
+/// still need packaging work are included, together with the crates they
+/// directly depend on.
+pub fn print(graph: &Graph, unpackaged: bool) -> Result<(), Error> {
+    let included = if unpackaged {
+        let mut included = HashSet::new();
+        for idx in graph.graph.node_indices() {
+            if needs_work(&graph.graph[idx]) {
+                included.insert(idx);
+                included.extend(graph.graph.neighbors(idx));
+            }
+        }
+        included
+    } else {
+        graph.graph.node_indices().collect::<HashSet<_>>()
+    };
+
+    let mut nodes = included.iter().copied().collect::<Vec<NodeIndex>>();
+    nodes.sort_by_key(|idx| &graph.graph[*idx].id);
+
+    println!("digraph dependencies {{");
+    println!("    node [shape=box, style=filled];");
+    for idx in nodes {
+        let pkg = &graph.graph[idx];
+        let status = pkg.packaging_status();
+        let font = if status == PackagingProgress::Blocker {
+            ", fontcolor=white"
+        } else {
+            ""
+        };
+        println!(
+            "    n{} [label=\"{}\", fillcolor={}{}];",
+            idx.index(),
+            label(pkg),
+            fill_color(status),
+            font
+        );
+    }
+
+    let mut edges = graph
+        .graph
+        .edge_references()
+        .filter(|edge| included.contains(&edge.source()) && included.contains(&edge.target()))
+        .filter(|edge| !unpackaged || needs_work(&graph.graph[edge.source()]))
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|edge| {
+        (
+            &graph.graph[edge.source()].id,
+            &graph.graph[edge.target()].id,
+        )
+    });
+
+    for edge in edges {
+        let style = match edge.weight().kind {
+            DependencyKind::Build => " [style=dashed, label=\"build\"]",
+            DependencyKind::Development => " [style=dotted, label=\"dev\"]",
+            _ => "",
+        };
+        println!(
+            "    n{} -> n{}{};",
+            edge.source().index(),
+            edge.target().index(),
+            style
+        );
+    }
+    println!("}}");
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use crate::errors::*;
+use crate::fedora::Pkg;
+use crate::graph::Graph;
+use cargo_metadata::DependencyKind;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use serde::Serialize;
+
+/// Bumped whenever a field is removed or changes its meaning
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    release: &'a str,
+    roots: Vec<&'a str>,
+    packages: Vec<Package<'a>>,
+    edges: Vec<Edge<'a>>,
+    unsatisfied_specs: Vec<UnsatisfiedSpec>,
+}
+
+#[derive(Serialize)]
+struct Package<'a> {
+    id: &'a str,
+    name: &'a str,
+    version: String,
+    source: Option<&'a str>,
+    license: Option<&'a str>,
+    repository: Option<&'a str>,
+    status: &'static str,
+    rpm: Option<Rpm<'a>>,
+    upstream: Option<Upstream>,
+    blocker: Option<String>,
+    features: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Rpm<'a> {
+    available: bool,
+    exact_match: bool,
+    compatible: bool,
+    outdated: bool,
+    pending: bool,
+    note: Option<&'a str>,
+    repo: &'a str,
+    spec: Option<String>,
+    version: Option<&'a str>,
+    package: Option<&'a str>,
+    evr: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Upstream {
+    version: String,
+    freshness: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    kind: &'static str,
+    targets: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct UnsatisfiedSpec {
+    spec: String,
+    build_requires: Vec<String>,
+}
+
+impl<'a> Package<'a> {
+    fn new(pkg: &'a Pkg) -> Package<'a> {
+        Package {
+            id: &pkg.id.repr,
+            name: &pkg.name,
+            version: pkg.version.to_string(),
+            source: pkg.source.as_ref().map(|source| source.repr.as_str()),
+            license: pkg.license.as_deref(),
+            repository: pkg.repository.as_deref(),
+            status: pkg.packaging_status().name(),
+            rpm: pkg.rpminfo.as_ref().map(|rpm| Rpm {
+                available: rpm.in_rawhide,
+                exact_match: rpm.exact_match,
+                compatible: rpm.compatible,
+                outdated: rpm.outdated,
+                pending: rpm.pending,
+                note: rpm.note.as_deref(),
+                repo: pkg.repo(),
+                spec: rpm.spec.as_ref().map(|spec| spec.display().to_string()),
+                version: (!rpm.version.is_empty()).then_some(rpm.version.as_str()),
+                package: (!rpm.package.is_empty()).then_some(rpm.package.as_str()),
+                evr: (!rpm.evr.is_empty()).then_some(rpm.evr.as_str()),
+            }),
+            upstream: pkg.upstream.as_ref().map(|version| Upstream {
+                version: version.to_string(),
+                freshness: pkg.freshness().map(|f| f.to_string()),
+            }),
+            blocker: pkg.blocker.as_ref().map(|blocker| blocker.to_string()),
+            features: &pkg.features,
+        }
+    }
+}
+
+pub fn print(graph: &Graph) -> Result<(), Error> {
+    let release = graph
+        .graph
+        .node_weights()
+        .next()
+        .map_or("rawhide", |pkg| pkg.rpmrelease.as_str());
+
+    let mut roots = graph
+        .roots()
+        .into_iter()
+        .map(|idx| graph.graph[idx].id.repr.as_str())
+        .collect::<Vec<_>>();
+    roots.sort();
+
+    let mut packages = graph
+        .graph
+        .node_weights()
+        .map(Package::new)
+        .collect::<Vec<_>>();
+    packages.sort_by_key(|pkg| pkg.id);
+
+    let mut edges = graph
+        .graph
+        .edge_references()
+        .map(|edge| Edge {
+            from: &graph.graph[edge.source()].id.repr,
+            to: &graph.graph[edge.target()].id.repr,
+            kind: match edge.weight().kind {
+                DependencyKind::Build => "build",
+                DependencyKind::Development => "dev",
+                _ => "normal",
+            },
+            targets: edge
+                .weight()
+                .targets
+                .as_ref()
+                .map(|targets| targets.iter().map(|t| t.to_string()).collect()),
+        })
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|edge| (edge.from, edge.to, edge.kind));
+
+    let unsatisfied_specs = graph
+        .unsatisfied_specs
+        .iter()
+        .map(|unsatisfied| UnsatisfiedSpec {
+            spec: unsatisfied.path.display().to_string(),
+            build_requires: unsatisfied
+                .requirements
+                .iter()
+                .map(|requirement| requirement.to_string())
+                .collect(),
+        })
+        .collect();
+
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        release,
+        roots,
+        packages,
+        edges,
+        unsatisfied_specs,
+    };
+    println!("{}", serde_json::to_string_pretty(&document)?);
+
+    Ok(())
+}
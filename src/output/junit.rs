@@ -0,0 +1,151 @@
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::graph::Graph;
+use std::fmt::Write;
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Why the test case of a package fails, `None` if it passes
+fn failure(pkg: &Pkg) -> Option<String> {
+    let rpm = pkg.rpminfo.as_ref();
+    match pkg.packaging_status() {
+        PackagingProgress::Missing => Some(format!(
+            "{} v{} is not packaged in {}",
+            pkg.name, pkg.version, pkg.rpmrelease
+        )),
+        PackagingProgress::NeedsUpdate => Some(format!(
+            "{} v{} is required, but {} only has {}",
+            pkg.name,
+            pkg.version,
+            pkg.rpmrelease,
+            rpm.map_or("an older version", |rpm| rpm.version.as_str())
+        )),
+        PackagingProgress::Blocker => Some(format!(
+            "{} v{} can't come from {}, it is taken from {}",
+            pkg.name,
+            pkg.version,
+            pkg.rpmrelease,
+            pkg.blocker
+                .as_ref()
+                .map_or(String::from("outside crates.io"), |b| b.to_string())
+        )),
+        _ => None,
+    }
+}
+
+pub fn print(graph: &Graph) -> Result<(), Error> {
+    let mut packages = graph.dependencies().collect::<Vec<_>>();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    let release = packages
+        .first()
+        .map_or("rawhide", |pkg| pkg.rpmrelease.as_str());
+    let specs = &graph.unsatisfied_specs;
+    let tests = packages.len() + specs.len();
+    let failures = packages.iter().filter(|pkg| failure(pkg).is_some()).count() + specs.len();
+    let skipped = packages.iter().filter(|pkg| pkg.patch_out).count();
+
+    let mut out = String::new();
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<testsuites name=\"cargo-rpmstatus\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        tests, failures, skipped
+    )?;
+    writeln!(
+        out,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        escape(release),
+        tests,
+        failures,
+        skipped
+    )?;
+
+    for pkg in packages {
+        let name = escape(&format!("{} v{}", pkg.name, pkg.version));
+        let classname = escape(&format!("{}.{}", release, pkg.name));
+        let status = pkg.packaging_status();
+
+        if let Some(message) = failure(pkg) {
+            let mut details = format!("status: {}\n", status.name());
+            if let Some(rpm) = &pkg.rpminfo
+                && rpm.in_rawhide
+            {
+                writeln!(details, "fedora version: {}", rpm.version)?;
+            }
+            if let Some(license) = &pkg.license {
+                writeln!(details, "license: {license}")?;
+            }
+            if let Some(repository) = &pkg.repository {
+                writeln!(details, "repository: {repository}")?;
+            }
+
+            writeln!(
+                out,
+                "    <testcase name=\"{name}\" classname=\"{classname}\">"
+            )?;
+            writeln!(
+                out,
+                "      <failure message=\"{}\" type=\"{}\">{}</failure>",
+                escape(&message),
+                status.name(),
+                escape(&details)
+            )?;
+            writeln!(out, "    </testcase>")?;
+        } else if pkg.patch_out {
+            writeln!(
+                out,
+                "    <testcase name=\"{name}\" classname=\"{classname}\">"
+            )?;
+            writeln!(
+                out,
+                "      <skipped message=\"only required on platforms Fedora doesn't build for\"/>"
+            )?;
+            writeln!(out, "    </testcase>")?;
+        } else {
+            writeln!(
+                out,
+                "    <testcase name=\"{name}\" classname=\"{classname}\"/>"
+            )?;
+        }
+    }
+
+    // a pending spec fails until everything it needs to build is available
+    for spec in specs {
+        let details = spec
+            .requirements
+            .iter()
+            .map(|requirement| format!("{requirement}\n"))
+            .collect::<String>();
+        writeln!(
+            out,
+            "    <testcase name=\"{}\" classname=\"{}.spec\">",
+            escape(&spec.file_name()),
+            escape(release)
+        )?;
+        writeln!(
+            out,
+            "      <failure message=\"{}\" type=\"{}\">{}</failure>",
+            escape(&format!(
+                "{} has BuildRequires missing in {}",
+                spec.file_name(),
+                release
+            )),
+            PackagingProgress::SpecPending.name(),
+            escape(&details)
+        )?;
+        writeln!(out, "    </testcase>")?;
+    }
+
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")?;
+
+    print!("{out}");
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::args::{OutputFormat, RpmArgs};
+use crate::errors::*;
+use crate::graph::Graph;
+use crate::tree;
+
+mod cyclonedx;
+mod dot;
+mod json;
+mod junit;
+mod report;
+
+pub fn print(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
+    match args.output {
+        OutputFormat::Tree => tree::print(args, graph),
+        OutputFormat::Json => json::print(graph),
+        OutputFormat::Dot => dot::print(graph, args.unpackaged),
+        OutputFormat::Markdown => report::print_markdown(args, graph),
+        OutputFormat::Html => report::print_html(args, graph),
+        OutputFormat::Junit => junit::print(graph),
+        OutputFormat::CycloneDx => cyclonedx::print(graph),
+    }
+}
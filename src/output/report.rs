@@ -0,0 +1,284 @@
+use crate::args::RpmArgs;
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::graph::Graph;
+use crate::spec::Unsatisfied;
+use crate::tree;
+use std::fmt::Write;
+
+const STATUSES: &[PackagingProgress] = &[
+    PackagingProgress::Available,
+    PackagingProgress::Compatible,
+    PackagingProgress::Pending,
+    PackagingProgress::SpecPending,
+    PackagingProgress::NeedsUpdate,
+    PackagingProgress::Missing,
+    PackagingProgress::PatchOut,
+    PackagingProgress::Blocker,
+];
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }
+th { background: #f0f0f0; }
+td.count { text-align: right; }
+pre { background: #f8f8f8; padding: 1em; overflow-x: auto; }
+.outdated { background: #fff4c2; }
+.missing { background: #fcdede; }";
+
+/// Everything shown in a report, collected once for both formats
+struct Report<'a> {
+    title: String,
+    release: &'a str,
+    counts: Vec<(PackagingProgress, usize)>,
+    work: Vec<&'a Pkg>,
+    specs: &'a [Unsatisfied],
+    tree: String,
+}
+
+impl<'a> Report<'a> {
+    fn new(args: &RpmArgs, graph: &'a Graph) -> Result<Report<'a>, Error> {
+        let mut packages = graph.dependencies().collect::<Vec<_>>();
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        let counts = STATUSES
+            .iter()
+            .map(|status| {
+                let count = packages
+                    .iter()
+                    .filter(|pkg| pkg.packaging_status() == *status)
+                    .count();
+                (*status, count)
+            })
+            .collect();
+
+        let work = packages
+            .into_iter()
+            .filter(|pkg| {
+                matches!(
+                    pkg.packaging_status(),
+                    PackagingProgress::Missing | PackagingProgress::NeedsUpdate
+                )
+            })
+            .collect();
+
+        let mut roots = graph
+            .roots()
+            .into_iter()
+            .map(|idx| graph.graph[idx].name.as_str())
+            .collect::<Vec<_>>();
+        roots.sort();
+
+        // the tree is embedded as plain text, so terminal colors would only add escape codes
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        colored::control::set_override(false);
+        let tree = tree::render(args, graph);
+        colored::control::set_override(colorize);
+
+        Ok(Report {
+            title: format!("Packaging status of {}", roots.join(", ")),
+            release: graph
+                .graph
+                .node_weights()
+                .next()
+                .map_or("rawhide", |pkg| pkg.rpmrelease.as_str()),
+            counts,
+            work,
+            specs: &graph.unsatisfied_specs,
+            tree: tree?,
+        })
+    }
+}
+
+fn fedora_version(pkg: &Pkg) -> &str {
+    pkg.rpminfo
+        .as_ref()
+        .filter(|rpm| !rpm.version.is_empty())
+        .map_or("-", |rpm| rpm.version.as_str())
+}
+
+fn markdown_escape(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+pub fn print_markdown(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
+    let report = Report::new(args, graph)?;
+    let mut out = String::new();
+
+    writeln!(out, "# {}", report.title)?;
+    writeln!(out)?;
+    writeln!(out, "Checked against Fedora {}.", report.release)?;
+    writeln!(out)?;
+
+    writeln!(out, "## Summary")?;
+    writeln!(out)?;
+    writeln!(out, "| Status | Crates |")?;
+    writeln!(out, "|---|---:|")?;
+    for (status, count) in &report.counts {
+        writeln!(out, "| {} | {} |", status.name(), count)?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "## Missing and outdated crates")?;
+    writeln!(out)?;
+    if report.work.is_empty() {
+        writeln!(out, "All crates are packaged.")?;
+    } else {
+        writeln!(
+            out,
+            "| Crate | Status | Required | Fedora | License | Repository |"
+        )?;
+        writeln!(out, "|---|---|---|---|---|---|")?;
+        for pkg in &report.work {
+            let repository = match &pkg.repository {
+                Some(url) => format!("<{url}>"),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                pkg.name,
+                pkg.packaging_status().name(),
+                pkg.version,
+                fedora_version(pkg),
+                markdown_escape(pkg.license.as_deref().unwrap_or("")),
+                repository
+            )?;
+        }
+    }
+    writeln!(out)?;
+
+    if !report.specs.is_empty() {
+        writeln!(out, "## Pending specs with missing BuildRequires")?;
+        writeln!(out)?;
+        for spec in report.specs {
+            let requirements = spec
+                .requirements
+                .iter()
+                .map(|requirement| format!("`{requirement}`"))
+                .collect::<Vec<_>>();
+            writeln!(out, "- {}: {}", spec.file_name(), requirements.join(", "))?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out, "## Dependency tree")?;
+    writeln!(out)?;
+    writeln!(out, "<details>")?;
+    writeln!(out, "<summary>Dependency tree</summary>")?;
+    writeln!(out)?;
+    writeln!(out, "```")?;
+    write!(out, "{}", report.tree)?;
+    writeln!(out, "```")?;
+    writeln!(out)?;
+    writeln!(out, "</details>")?;
+
+    print!("{out}");
+    Ok(())
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn print_html(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
+    let report = Report::new(args, graph)?;
+    let title = html_escape(&report.title);
+    let mut out = String::new();
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{title}</title>")?;
+    writeln!(out, "<style>\n{STYLE}\n</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>{title}</h1>")?;
+    writeln!(
+        out,
+        "<p>Checked against Fedora {}.</p>",
+        html_escape(report.release)
+    )?;
+
+    writeln!(out, "<h2>Summary</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>Status</th><th>Crates</th></tr>")?;
+    for (status, count) in &report.counts {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"count\">{}</td></tr>",
+            status.name(),
+            count
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<h2>Missing and outdated crates</h2>")?;
+    if report.work.is_empty() {
+        writeln!(out, "<p>All crates are packaged.</p>")?;
+    } else {
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Crate</th><th>Status</th><th>Required</th><th>Fedora</th><th>License</th><th>Repository</th></tr>"
+        )?;
+        for pkg in &report.work {
+            let status = pkg.packaging_status().name();
+            let repository = match &pkg.repository {
+                Some(url) => {
+                    let url = html_escape(url);
+                    format!("<a href=\"{url}\">{url}</a>")
+                }
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                status,
+                html_escape(&pkg.name),
+                status,
+                pkg.version,
+                html_escape(fedora_version(pkg)),
+                html_escape(pkg.license.as_deref().unwrap_or("")),
+                repository
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if !report.specs.is_empty() {
+        writeln!(out, "<h2>Pending specs with missing BuildRequires</h2>")?;
+        writeln!(out, "<ul>")?;
+        for spec in report.specs {
+            let requirements = spec
+                .requirements
+                .iter()
+                .map(|requirement| {
+                    format!("<code>{}</code>", html_escape(&requirement.to_string()))
+                })
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "<li>{}: {}</li>",
+                html_escape(&spec.file_name()),
+                requirements.join(", ")
+            )?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "<h2>Dependency tree</h2>")?;
+    writeln!(out, "<details>")?;
+    writeln!(out, "<summary>Dependency tree</summary>")?;
+    writeln!(out, "<pre>{}</pre>", html_escape(&report.tree))?;
+    writeln!(out, "</details>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+
+    print!("{out}");
+    Ok(())
+}
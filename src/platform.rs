@@ -0,0 +1,176 @@
+use crate::fedora::Pkg;
+use crate::graph::Graph;
+use cargo_metadata::DependencyKind;
+use cargo_platform::{Cfg, Platform};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+use std::sync::LazyLock;
+
+struct Target {
+    triple: &'static str,
+    arch: &'static str,
+    pointer_width: &'static str,
+    endian: &'static str,
+}
+
+/// Linux targets Fedora builds Rust packages for
+static FEDORA_TARGETS: &[Target] = &[
+    Target {
+        triple: "x86_64-unknown-linux-gnu",
+        arch: "x86_64",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "i686-unknown-linux-gnu",
+        arch: "x86",
+        pointer_width: "32",
+        endian: "little",
+    },
+    Target {
+        triple: "aarch64-unknown-linux-gnu",
+        arch: "aarch64",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "powerpc64le-unknown-linux-gnu",
+        arch: "powerpc64",
+        pointer_width: "64",
+        endian: "little",
+    },
+    Target {
+        triple: "s390x-unknown-linux-gnu",
+        arch: "s390x",
+        pointer_width: "64",
+        endian: "big",
+    },
+];
+
+static FEDORA_CFGS: LazyLock<Vec<(&'static str, Vec<Cfg>)>> = LazyLock::new(|| {
+    FEDORA_TARGETS
+        .iter()
+        .map(|target| (target.triple, target.cfgs()))
+        .collect()
+});
+
+impl Target {
+    fn cfgs(&self) -> Vec<Cfg> {
+        let pair = |key: &str, value: &str| Cfg::KeyPair(key.to_string(), value.to_string());
+        vec![
+            Cfg::Name("unix".to_string()),
+            pair("target_os", "linux"),
+            pair("target_family", "unix"),
+            pair("target_env", "gnu"),
+            pair("target_vendor", "unknown"),
+            pair("target_arch", self.arch),
+            pair("target_pointer_width", self.pointer_width),
+            pair("target_endian", self.endian),
+            pair("panic", "unwind"),
+        ]
+    }
+}
+
+/// Whether a target-specific dependency applies to any Linux target supported by Fedora
+pub fn matches_fedora(platform: &Platform) -> bool {
+    FEDORA_CFGS
+        .iter()
+        .any(|(triple, cfgs)| platform.matches(triple, cfgs))
+}
+
+/// Mark every package that is only reachable through dependencies on other
+/// platforms, so it can be patched out instead of packaged
+pub fn mark_patch_out(graph: &mut Graph) {
+    let mut stack = graph.roots();
+
+    let mut needed = vec![false; graph.graph.node_bound()];
+    while let Some(idx) = stack.pop() {
+        if needed[idx.index()] {
+            continue;
+        }
+        needed[idx.index()] = true;
+
+        for edge in graph.graph.edges(idx) {
+            if edge.weight().on_fedora() {
+                stack.push(edge.target());
+            }
+        }
+    }
+
+    for idx in graph.graph.node_indices().collect::<Vec<_>>() {
+        graph.graph[idx].patch_out = !needed[idx.index()];
+    }
+}
+
+/// A dependency declaration a rust2rpm patch has to remove from a manifest
+pub struct Patch<'a> {
+    pub package: &'a Pkg,
+    pub dependency: &'a Pkg,
+    pub section: String,
+}
+
+/// Collect the target-specific dependency sections of packaged crates that
+/// don't apply to Fedora
+pub fn patches(graph: &Graph) -> Vec<Patch<'_>> {
+    let mut patches = vec![];
+
+    for edge in graph.graph.edge_references() {
+        let package = &graph.graph[edge.source()];
+        if package.patch_out {
+            continue;
+        }
+
+        let Some(targets) = &edge.weight().targets else {
+            continue;
+        };
+        if edge.weight().on_fedora() {
+            continue;
+        }
+
+        let table = match edge.weight().kind {
+            DependencyKind::Build => "build-dependencies",
+            DependencyKind::Development => "dev-dependencies",
+            _ => "dependencies",
+        };
+        for target in targets {
+            patches.push(Patch {
+                package,
+                dependency: &graph.graph[edge.target()],
+                section: format!("target.'{target}'.{table}"),
+            });
+        }
+    }
+
+    patches.sort_by(|a, b| {
+        (&a.package.id, &a.section, &a.dependency.name).cmp(&(
+            &b.package.id,
+            &b.section,
+            &b.dependency.name,
+        ))
+    });
+    patches
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::matches_fedora;
+    use cargo_platform::Platform;
+
+    fn matches(s: &str) -> bool {
+        matches_fedora(&s.parse::<Platform>().unwrap())
+    }
+
+    #[test]
+    fn fedora_targets() {
+        assert!(matches("cfg(unix)"));
+        assert!(matches("cfg(target_os = \"linux\")"));
+        assert!(matches("cfg(target_arch = \"s390x\")"));
+        assert!(matches("cfg(not(target_arch = \"wasm32\"))"));
+        assert!(matches("aarch64-unknown-linux-gnu"));
+        assert!(!matches("cfg(windows)"));
+        assert!(!matches("cfg(target_os = \"macos\")"));
+        assert!(!matches(
+            "cfg(any(target_os = \"ios\", target_os = \"android\"))"
+        ));
+        assert!(!matches("x86_64-pc-windows-msvc"));
+    }
+}
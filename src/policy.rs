@@ -0,0 +1,193 @@
+use crate::errors::*;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::graph::{Graph, PkgSpec};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Looked up in the current directory if `--policy` isn't given
+pub const DEFAULT_PATH: &str = "rpmstatus.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    deny: Vec<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    max_missing: Option<usize>,
+}
+
+/// Rules deciding whether the packaging status of a project is acceptable
+#[derive(Debug, Default)]
+pub struct Policy {
+    /// Statuses that fail the check
+    pub deny: Vec<PackagingProgress>,
+    /// Crates exempt from every rule, e.g. because they are already being packaged
+    pub allow: Vec<PkgSpec>,
+    /// Number of missing crates tolerated
+    pub max_missing: Option<usize>,
+}
+
+pub struct Violation<'a> {
+    pub package: &'a Pkg,
+    pub status: PackagingProgress,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Policy, Error> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read policy file {}", path.display()))?;
+        Policy::parse(&content).with_context(|| format!("invalid policy file {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Policy, Error> {
+        let file = toml::from_str::<PolicyFile>(content)?;
+
+        let deny = file
+            .deny
+            .iter()
+            .map(|status| {
+                status
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("{err} `{status}` in deny"))
+            })
+            .collect::<Result<_, Error>>()?;
+        let allow = file
+            .allow
+            .iter()
+            .map(|spec| spec.parse())
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Policy {
+            deny,
+            allow,
+            max_missing: file.max_missing,
+        })
+    }
+
+    /// Collect every crate breaking a rule, sorted by name
+    pub fn evaluate<'a>(&self, graph: &'a Graph) -> Vec<Violation<'a>> {
+        let mut packages = graph
+            .dependencies()
+            .filter(|pkg| !self.allow.iter().any(|spec| spec.matches(pkg)))
+            .collect::<Vec<_>>();
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        // missing crates only fail the check once there are more than tolerated
+        let missing = packages
+            .iter()
+            .filter(|pkg| pkg.packaging_status() == PackagingProgress::Missing)
+            .count();
+        let missing_denied = match self.max_missing {
+            Some(max) => missing > max,
+            None => self.deny.contains(&PackagingProgress::Missing),
+        };
+
+        packages
+            .into_iter()
+            .filter_map(|package| {
+                let status = package.packaging_status();
+                let denied = if status == PackagingProgress::Missing {
+                    missing_denied
+                } else {
+                    self.deny.contains(&status)
+                };
+                denied.then_some(Violation { package, status })
+            })
+            .collect()
+    }
+
+    /// Print the violations to stderr, returns whether the check passed
+    pub fn report(&self, graph: &Graph) -> bool {
+        let violations = self.evaluate(graph);
+        if violations.is_empty() {
+            return true;
+        }
+
+        eprintln!("Packaging policy violations:");
+        for violation in &violations {
+            eprintln!(
+                " {} {} v{} is {}",
+                violation.status,
+                violation.package.name,
+                violation.package.version,
+                violation.status.name()
+            );
+        }
+        if let Some(max) = self.max_missing {
+            let missing = violations
+                .iter()
+                .filter(|v| v.status == PackagingProgress::Missing)
+                .count();
+            if missing > 0 {
+                eprintln!("{missing} crates are missing, at most {max} are allowed");
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::RpmArgs;
+    use crate::graph;
+    use crate::policy::Policy;
+    use std::path::Path;
+
+    #[test]
+    fn evaluate_policy() {
+        let lockfile = toml::from_str(
+            r#"
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+            dependencies = ["foo", "bar", "baz"]
+
+            [[package]]
+            name = "foo"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "bar"
+            version = "1.2.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "baz"
+            version = "0.3.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        )
+        .unwrap();
+        let mut graph =
+            graph::from_lockfile(&RpmArgs::default(), Path::new("Cargo.lock"), lockfile).unwrap();
+        // the unpackaged app is the root, not a dependency
+        graph.root = graph.members.pop();
+
+        let names = |policy: &Policy| {
+            policy
+                .evaluate(&graph)
+                .iter()
+                .map(|v| v.package.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let policy = Policy::parse("deny = [\"missing\"]\nallow = [\"foo\", \"bar@1\"]").unwrap();
+        assert_eq!(names(&policy), "baz");
+
+        let policy = Policy::parse("deny = [\"missing\", \"blocker\"]").unwrap();
+        assert_eq!(names(&policy), "bar baz foo");
+
+        let policy = Policy::parse("deny = [\"missing\"]\nmax_missing = 3").unwrap();
+        assert_eq!(names(&policy), "");
+
+        let policy = Policy::parse("max_missing = 1\nallow = [\"baz\"]").unwrap();
+        assert_eq!(names(&policy), "bar foo");
+
+        assert!(Policy::parse("deny = [\"gone\"]").is_err());
+        assert!(Policy::parse("denied = []").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::errors::*;
+use crate::graph::Graph;
+use semver::Version;
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// How far the Fedora package lags behind the newest release on crates.io
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Freshness {
+    Current,
+    PatchBehind,
+    MinorBehind,
+    MajorBehind,
+}
+
+impl fmt::Display for Freshness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Freshness::Current => "current",
+            Freshness::PatchBehind => "patch-behind",
+            Freshness::MinorBehind => "minor-behind",
+            Freshness::MajorBehind => "semver-major-behind",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Freshness {
+    pub fn compare(packaged: &Version, upstream: &Version) -> Freshness {
+        if packaged >= upstream {
+            Freshness::Current
+        } else if semver_epoch(packaged) != semver_epoch(upstream) {
+            Freshness::MajorBehind
+        } else if packaged.major == upstream.major && packaged.minor == upstream.minor {
+            Freshness::PatchBehind
+        } else {
+            Freshness::MinorBehind
+        }
+    }
+
+    pub fn needs_bump(&self) -> bool {
+        *self != Freshness::Current
+    }
+}
+
+/// The leftmost non-zero component, which cargo treats as the compatibility boundary
+pub fn semver_epoch(version: &Version) -> (u64, u64, u64) {
+    if version.major != 0 {
+        (version.major, 0, 0)
+    } else if version.minor != 0 {
+        (0, version.minor, 0)
+    } else {
+        (0, 0, version.patch)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+fn cargo_home() -> Result<PathBuf> {
+    if let Some(home) = env::var_os("CARGO_HOME") {
+        return Ok(PathBuf::from(home));
+    }
+
+    let home = dirs::home_dir().context("home directory not found")?;
+    Ok(home.join(".cargo"))
+}
+
+fn index_path(name: &str) -> PathBuf {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => PathBuf::from("1").join(&name),
+        2 => PathBuf::from("2").join(&name),
+        3 => PathBuf::from("3").join(&name[..1]).join(&name),
+        _ => PathBuf::from(&name[..2]).join(&name[2..4]).join(&name),
+    }
+}
+
+/// Parse a cargo index cache file: a version byte, the index format version
+/// as u32, the revision, then pairs of version and json entry, all separated
+/// by NUL bytes
+fn parse_cache(data: &[u8]) -> Option<Version> {
+    let data = data.get(5..)?;
+    let mut fields = data.split(|b| *b == 0).skip(1);
+
+    let mut newest: Option<Version> = None;
+    while let (Some(_), Some(json)) = (fields.next(), fields.next()) {
+        let Ok(entry) = serde_json::from_slice::<IndexEntry>(json) else {
+            continue;
+        };
+        if entry.yanked {
+            continue;
+        }
+        let Ok(version) = Version::parse(&entry.vers) else {
+            continue;
+        };
+        if !version.pre.is_empty() {
+            continue;
+        }
+        if newest.as_ref().is_none_or(|newest| version > *newest) {
+            newest = Some(version);
+        }
+    }
+
+    newest
+}
+
+pub struct Index {
+    caches: Vec<PathBuf>,
+}
+
+impl Index {
+    pub fn open() -> Result<Index> {
+        let index_dir = cargo_home()?.join("registry").join("index");
+
+        let mut caches = vec![];
+        if index_dir.is_dir() {
+            for entry in fs::read_dir(&index_dir)
+                .with_context(|| format!("could not read {}", index_dir.display()))?
+            {
+                let cache = entry?.path().join(".cache");
+                if cache.is_dir() {
+                    caches.push(cache);
+                }
+            }
+        }
+
+        if caches.is_empty() {
+            warn!("No local registry index found in {}", index_dir.display());
+        }
+
+        Ok(Index { caches })
+    }
+
+    /// Newest non-yanked, non-prerelease version of a crate in the local index
+    pub fn newest(&self, name: &str) -> Option<Version> {
+        let path = index_path(name);
+        self.caches
+            .iter()
+            .filter_map(|cache| fs::read(cache.join(&path)).ok())
+            .filter_map(|data| parse_cache(&data))
+            .max()
+    }
+}
+
+pub fn populate(graph: &mut Graph) -> Result<(), Error> {
+    let index = Index::open()?;
+
+    let idxs = graph.graph.node_indices().collect::<Vec<_>>();
+    for idx in idxs {
+        let pkg = &mut graph.graph[idx];
+        pkg.upstream = index.newest(&pkg.name);
+        debug!(
+            "Newest upstream version of {}: {:?}",
+            pkg.name, pkg.upstream
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{index_path, parse_cache, Freshness};
+    use semver::Version;
+    use std::path::PathBuf;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn freshness_follows_semver_boundaries() {
+        assert_eq!(
+            Freshness::compare(&v("1.2.3"), &v("1.2.3")),
+            Freshness::Current
+        );
+        assert_eq!(
+            Freshness::compare(&v("1.3.0"), &v("1.2.3")),
+            Freshness::Current
+        );
+        assert_eq!(
+            Freshness::compare(&v("1.2.3"), &v("1.2.9")),
+            Freshness::PatchBehind
+        );
+        assert_eq!(
+            Freshness::compare(&v("1.2.3"), &v("1.5.0")),
+            Freshness::MinorBehind
+        );
+        assert_eq!(
+            Freshness::compare(&v("1.2.3"), &v("2.0.0")),
+            Freshness::MajorBehind
+        );
+        assert_eq!(
+            Freshness::compare(&v("0.3.1"), &v("0.3.4")),
+            Freshness::PatchBehind
+        );
+        assert_eq!(
+            Freshness::compare(&v("0.3.1"), &v("0.4.0")),
+            Freshness::MajorBehind
+        );
+    }
+
+    #[test]
+    fn index_paths() {
+        assert_eq!(index_path("a"), PathBuf::from("1/a"));
+        assert_eq!(index_path("cc"), PathBuf::from("2/cc"));
+        assert_eq!(index_path("syn"), PathBuf::from("3/s/syn"));
+        assert_eq!(index_path("Serde"), PathBuf::from("se/rd/serde"));
+    }
+
+    #[test]
+    fn parse_index_cache() {
+        let mut data = vec![3, 2, 0, 0, 0];
+        for (vers, yanked) in [
+            ("1.0.0", false),
+            ("1.2.0", false),
+            ("1.3.0", true),
+            ("2.0.0-rc.1", false),
+        ] {
+            data.extend_from_slice(b"\0");
+            data.extend_from_slice(vers.as_bytes());
+            data.extend_from_slice(b"\0");
+            data.extend_from_slice(
+                format!(r#"{{"name":"foo","vers":"{vers}","yanked":{yanked}}}"#).as_bytes(),
+            );
+        }
+        // the revision comes first, the leading NUL of the loop above terminates it
+        data.splice(5..5, b"etag".iter().copied());
+
+        assert_eq!(parse_cache(&data), Some(v("1.2.0")));
+    }
+}
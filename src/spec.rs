@@ -0,0 +1,390 @@
+use crate::db::{self, Connection};
+use crate::errors::*;
+use crate::fedora::Pkg;
+use crate::graph::Graph;
+use semver::VersionReq;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A `crate(...)` dependency from a `BuildRequires:` line
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    /// e.g. `crate(serde/default)`
+    pub name: String,
+    pub req: VersionReq,
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.req == VersionReq::STAR {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} {}", self.name, self.req)
+        }
+    }
+}
+
+/// A work-in-progress spec generated by rust2rpm
+#[derive(Debug, Clone)]
+pub struct Spec {
+    pub path: PathBuf,
+    /// The crate packaged by the spec, from `%global crate`
+    pub krate: String,
+    pub version: String,
+    /// `crate(foo)` and `crate(foo/feature)`, implied by the `%package` sections
+    pub provides: Vec<String>,
+    pub build_requires: Vec<Requirement>,
+}
+
+/// A pending spec with BuildRequires that neither the release nor another pending spec provides
+#[derive(Debug, Clone)]
+pub struct Unsatisfied {
+    pub path: PathBuf,
+    pub requirements: Vec<Requirement>,
+}
+
+impl Unsatisfied {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Expand `%name`, `%{name}` and `%{?name}` with the macros defined so far
+fn expand(s: &str, macros: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(pos) = rest.find('%') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        let (name, len) = if let Some(braced) = rest.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (braced[..end].trim_start_matches('?'), end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], end)
+        };
+
+        match macros.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                out.push('%');
+                out.push_str(&rest[..len]);
+            }
+        }
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Turn rpm comparisons like `>= 1.0.0` and `< 2.0.0~` into a semver requirement
+fn version_req(comparisons: &[(&str, &str)]) -> Option<VersionReq> {
+    if comparisons.is_empty() {
+        return Some(VersionReq::STAR);
+    }
+
+    let req = comparisons
+        .iter()
+        .map(|(op, version)| {
+            // `~` sorts before anything, so `< 2.0.0~` excludes 2.0.0 pre-releases as well
+            let version = version.trim_end_matches('~').replace('~', "-");
+            format!("{op}{version}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    VersionReq::parse(&req).ok()
+}
+
+/// Parse the `crate(...)` requirements of a `BuildRequires:` or `Requires:` value, either
+/// `crate(foo) >= 1.0` or a rich dependency like `(crate(foo) >= 1.0 with crate(foo) < 2.0.0~)`
+pub fn parse_requirements(value: &str) -> Vec<Requirement> {
+    let words = value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut requirements: Vec<(&str, Vec<(&str, &str)>)> = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i].trim_start_matches('(');
+        i += 1;
+        if !word.starts_with("crate(") {
+            continue;
+        }
+        let Some(end) = word.find(')') else {
+            continue;
+        };
+        let name = &word[..=end];
+
+        let comparison = match words.get(i..i + 2) {
+            Some([op, version]) if ["=", "<", "<=", ">", ">="].contains(op) => {
+                i += 2;
+                Some((*op, version.trim_end_matches(')')))
+            }
+            _ => None,
+        };
+        match requirements.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, comparisons)) => comparisons.extend(comparison),
+            None => requirements.push((name, comparison.into_iter().collect())),
+        }
+    }
+
+    requirements
+        .into_iter()
+        .filter_map(|(name, comparisons)| {
+            Some(Requirement {
+                name: name.to_string(),
+                req: version_req(&comparisons)?,
+            })
+        })
+        .collect()
+}
+
+pub fn parse(path: &Path, content: &str) -> Result<Spec, Error> {
+    let mut macros = HashMap::new();
+    let mut name = None;
+    let mut version = None;
+    let mut provides = vec![];
+    let mut build_requires = vec![];
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line
+            .strip_prefix("%global")
+            .or_else(|| line.strip_prefix("%define"))
+        {
+            if let Some((key, value)) = rest.trim().split_once(char::is_whitespace) {
+                let value = expand(value.trim(), &macros);
+                macros.insert(key.to_string(), value);
+            }
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("%package") {
+            let (Some(krate), Some(name)) = (macros.get("crate"), &name) else {
+                continue;
+            };
+            let rest = expand(rest.trim(), &macros);
+            let package = match rest.strip_prefix("-n") {
+                Some(package) => package.trim().to_string(),
+                None => format!("{name}-{rest}"),
+            };
+
+            if package == format!("{name}-devel") {
+                provides.push(format!("crate({krate})"));
+            } else if let Some(feature) = package
+                .strip_prefix(&format!("{name}+"))
+                .and_then(|p| p.strip_suffix("-devel"))
+            {
+                provides.push(format!("crate({krate}/{feature})"));
+            }
+            continue;
+        }
+
+        let Some((tag, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = expand(value.trim(), &macros);
+        match tag.trim() {
+            "Name" => {
+                macros.insert("name".to_string(), value.clone());
+                name = Some(value);
+            }
+            "Version" => {
+                macros.insert("version".to_string(), value.clone());
+                version = Some(value);
+            }
+            "BuildRequires" => build_requires.extend(parse_requirements(&value)),
+            _ => {}
+        }
+    }
+
+    let krate = match macros.get("crate") {
+        Some(krate) => krate.clone(),
+        None => name
+            .as_deref()
+            .and_then(|name| name.strip_prefix("rust-"))
+            .context("spec doesn't define %global crate")?
+            .to_string(),
+    };
+
+    Ok(Spec {
+        path: path.to_path_buf(),
+        krate,
+        version: version.context("spec without Version:")?,
+        provides,
+        build_requires,
+    })
+}
+
+fn spec_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() && depth > 0 {
+            spec_files(&path, depth - 1, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "spec") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Read the specs in `dir` and its direct subdirectories, as in a repository per package
+pub fn load(dir: &Path) -> Result<Vec<Spec>, Error> {
+    let mut files = vec![];
+    spec_files(dir, 1, &mut files)?;
+    files.sort();
+
+    let mut specs = vec![];
+    for path in files {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        match parse(&path, &content) {
+            Ok(spec) => {
+                debug!("Read spec {}: {:?}", path.display(), spec);
+                specs.push(spec);
+            }
+            Err(err) => warn!("Skipping {}: {:#}", path.display(), err),
+        }
+    }
+    info!("Read {} specs from {}", specs.len(), dir.display());
+
+    Ok(specs)
+}
+
+/// The spec packaging `pkg` at a version satisfying it
+fn find<'a>(specs: &'a [Spec], pkg: &Pkg) -> Option<&'a Spec> {
+    let (exact, _) = db::requirements(&pkg.version).ok()?;
+    specs.iter().find(|spec| {
+        spec.krate == pkg.name && db::is_compatible(&spec.version, &exact).unwrap_or(false)
+    })
+}
+
+/// Mark crates without a usable package in the repository as having a pending spec
+pub fn populate(graph: &mut Graph, specs: &[Spec]) {
+    for pkg in graph.graph.node_weights_mut() {
+        let Some(spec) = find(specs, pkg) else {
+            continue;
+        };
+        let Some(rpm) = &mut pkg.rpminfo else {
+            continue;
+        };
+
+        if (rpm.in_rawhide && !rpm.outdated) || rpm.pending {
+            continue;
+        }
+        rpm.spec = Some(spec.path.clone());
+    }
+}
+
+/// BuildRequires of the specs neither provided by the release nor by another pending spec
+pub fn unsatisfied(specs: &[Spec], rpmrelease: &String) -> Result<Vec<Unsatisfied>, Error> {
+    let mut db = Connection::new(rpmrelease)?;
+
+    let mut unsatisfied = vec![];
+    for spec in specs {
+        let mut missing = vec![];
+        for requirement in &spec.build_requires {
+            let by_spec = specs.iter().any(|other| {
+                other.provides.contains(&requirement.name)
+                    && db::is_compatible(&other.version, &requirement.req).unwrap_or(false)
+            });
+            if by_spec {
+                continue;
+            }
+
+            let by_release = db
+                .provides(&requirement.name)?
+                .iter()
+                .any(|version| db::is_compatible(version, &requirement.req).unwrap_or(false));
+            if !by_release {
+                missing.push(requirement.clone());
+            }
+        }
+
+        if !missing.is_empty() {
+            unsatisfied.push(Unsatisfied {
+                path: spec.path.clone(),
+                requirements: missing,
+            });
+        }
+    }
+
+    Ok(unsatisfied)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parse;
+    use semver::Version;
+    use std::path::Path;
+
+    #[test]
+    fn parse_rust2rpm_spec() {
+        let spec = parse(
+            Path::new("rust-foo.spec"),
+            r#"
+# Generated by rust2rpm 26
+%bcond check 1
+%global debug_package %{nil}
+
+%global crate foo
+%global crate_version 0.4.0-rc.1
+
+Name:           rust-%{crate}
+Version:        0.4.0~rc.1
+Release:        %autorelease
+
+BuildRequires:  cargo-rpm-macros >= 24
+BuildRequires:  (crate(serde/default) >= 1.0.100 with crate(serde/default) < 2.0.0~)
+BuildRequires:  crate(log) >= 0.4
+BuildRequires:  crate(once_cell/default)
+
+%package        devel
+Summary:        %{summary}
+
+%package     -n %{name}+default-devel
+Summary:        %{summary}
+
+%package     -n %{name}+std-devel
+Summary:        %{summary}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(spec.krate, "foo");
+        assert_eq!(spec.version, "0.4.0~rc.1");
+        assert_eq!(
+            spec.provides,
+            ["crate(foo)", "crate(foo/default)", "crate(foo/std)"]
+        );
+
+        let reqs = &spec.build_requires;
+        assert_eq!(reqs.len(), 3);
+        assert_eq!(reqs[0].name, "crate(serde/default)");
+        assert_eq!(
+            reqs[0].to_string(),
+            "crate(serde/default) >=1.0.100, <2.0.0"
+        );
+        assert!(reqs[0].req.matches(&Version::parse("1.0.200").unwrap()));
+        assert!(!reqs[0].req.matches(&Version::parse("2.0.0").unwrap()));
+        assert!(!reqs[1].req.matches(&Version::parse("0.3.0").unwrap()));
+        assert_eq!(reqs[2].to_string(), "crate(once_cell/default)");
+    }
+}
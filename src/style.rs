@@ -0,0 +1,174 @@
+use crate::args::{IconTheme, OutputFormat, RpmArgs};
+use crate::errors::*;
+use crate::fedora::PackagingProgress;
+use colored::{Color, ColoredString, Colorize};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// How the name of a crate with a given status is highlighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    Plain,
+    Dimmed,
+    Color(Color),
+}
+
+impl FromStr for Paint {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Paint, &'static str> {
+        match s {
+            "plain" | "none" => Ok(Paint::Plain),
+            "dimmed" => Ok(Paint::Dimmed),
+            _ => Color::from_str(s)
+                .map(Paint::Color)
+                .map_err(|_| "invalid color"),
+        }
+    }
+}
+
+struct Style {
+    icons: IconTheme,
+    colors: Vec<(PackagingProgress, Paint)>,
+    hyperlinks: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            icons: IconTheme::Emoji,
+            colors: vec![],
+            hyperlinks: false,
+        }
+    }
+}
+
+/// Decide whether to color our own output: `--color` wins over `CARGO_TERM_COLOR`,
+/// which wins over `NO_COLOR`, otherwise colors are only used on a terminal
+fn should_colorize(args: &RpmArgs) -> Result<bool, Error> {
+    let when = args
+        .color
+        .clone()
+        .or_else(|| env::var("CARGO_TERM_COLOR").ok());
+
+    match when.as_deref() {
+        Some("always") => Ok(true),
+        Some("never") => Ok(false),
+        Some("auto") | None => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            Ok(!no_color && io::stdout().is_terminal())
+        }
+        Some(other) => bail!("invalid color setting `{other}`, expected auto, always or never"),
+    }
+}
+
+/// Terminals known to render OSC 8 hyperlinks
+fn terminal_supports_hyperlinks() -> bool {
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    if ["iTerm.app", "WezTerm", "vscode", "ghostty"].contains(&program.as_str()) {
+        return true;
+    }
+    if env::var("VTE_VERSION")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+    {
+        return true;
+    }
+    ["KITTY_WINDOW_ID", "WT_SESSION", "DOMTERM"]
+        .iter()
+        .any(|var| env::var_os(var).is_some())
+}
+
+/// Apply the color and theme settings, before anything is printed
+pub fn init(args: &RpmArgs) -> Result<(), Error> {
+    colored::control::set_override(should_colorize(args)?);
+
+    let style = Style {
+        icons: args.icons,
+        colors: args
+            .status_color
+            .iter()
+            .map(|sc| (sc.status, sc.paint))
+            .collect(),
+        // escape sequences would end up verbatim in files, pipes and reports
+        hyperlinks: matches!(args.output, OutputFormat::Tree)
+            && io::stdout().is_terminal()
+            && (args.hyperlinks || terminal_supports_hyperlinks()),
+    };
+    if STYLE.set(style).is_err() {
+        warn!("Output style was already initialized");
+    }
+
+    Ok(())
+}
+
+fn style() -> &'static Style {
+    STYLE.get_or_init(Style::default)
+}
+
+pub fn icon(status: PackagingProgress) -> &'static str {
+    match style().icons {
+        IconTheme::Emoji => match status {
+            PackagingProgress::Available => "  ",
+            PackagingProgress::Compatible => "  ",
+            PackagingProgress::Pending => "📝",
+            PackagingProgress::SpecPending => "📄",
+            PackagingProgress::NeedsUpdate => "⌛",
+            PackagingProgress::Missing => "🔴",
+            PackagingProgress::PatchOut => "🩹",
+            PackagingProgress::Blocker => "⛔",
+        },
+        IconTheme::Ascii => match status {
+            PackagingProgress::Available => "   ",
+            PackagingProgress::Compatible => "   ",
+            PackagingProgress::Pending => "[~]",
+            PackagingProgress::SpecPending => "[S]",
+            PackagingProgress::NeedsUpdate => "[U]",
+            PackagingProgress::Missing => "[M]",
+            PackagingProgress::PatchOut => "[P]",
+            PackagingProgress::Blocker => "[B]",
+        },
+        IconTheme::None => "",
+    }
+}
+
+fn default_paint(status: PackagingProgress) -> Paint {
+    match status {
+        PackagingProgress::Available | PackagingProgress::Compatible => Paint::Color(Color::Green),
+        PackagingProgress::Pending | PackagingProgress::SpecPending => Paint::Color(Color::Cyan),
+        PackagingProgress::NeedsUpdate => Paint::Color(Color::Yellow),
+        PackagingProgress::PatchOut => Paint::Dimmed,
+        PackagingProgress::Missing | PackagingProgress::Blocker => Paint::Plain,
+    }
+}
+
+/// Highlight text, usually a crate name, in the color configured for its status
+pub fn paint(status: PackagingProgress, text: &str) -> ColoredString {
+    let paint = style()
+        .colors
+        .iter()
+        .rev()
+        .find(|(s, _)| *s == status)
+        .map_or_else(|| default_paint(status), |(_, paint)| *paint);
+
+    match paint {
+        Paint::Plain => text.normal(),
+        Paint::Dimmed => text.dimmed(),
+        Paint::Color(color) => text.color(color),
+    }
+}
+
+/// Turn text into an OSC 8 hyperlink, if enabled
+pub fn link(url: Option<&str>, text: &str) -> String {
+    match url {
+        Some(url) if style().hyperlinks && !text.is_empty() => {
+            format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+        }
+        _ => text.to_string(),
+    }
+}
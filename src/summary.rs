@@ -0,0 +1,230 @@
+use crate::args::RpmArgs;
+use crate::errors::*;
+use crate::fedora::{self, PackagingProgress, Pkg};
+use crate::graph::Graph;
+use petgraph::graph::NodeIndex;
+use std::collections::{HashMap, HashSet};
+
+const STATUSES: &[PackagingProgress] = &[
+    PackagingProgress::Available,
+    PackagingProgress::Compatible,
+    PackagingProgress::Pending,
+    PackagingProgress::SpecPending,
+    PackagingProgress::NeedsUpdate,
+    PackagingProgress::Missing,
+    PackagingProgress::PatchOut,
+    PackagingProgress::Blocker,
+];
+
+pub struct Stats {
+    pub release: String,
+    pub counts: Vec<(PackagingProgress, usize)>,
+    /// Distinct crate names, without the project itself
+    pub crates: usize,
+    /// Distinct packages, a crate required at several versions counts once per version
+    pub nodes: usize,
+    /// Most crates that have to be packaged one after another
+    pub longest_chain: usize,
+}
+
+fn needs_work(pkg: &Pkg) -> bool {
+    matches!(
+        pkg.packaging_status(),
+        PackagingProgress::Missing | PackagingProgress::NeedsUpdate | PackagingProgress::Blocker
+    )
+}
+
+/// Length of the longest dependency chain of unpackaged crates starting at `idx`
+fn chain(
+    graph: &Graph,
+    idx: NodeIndex,
+    lengths: &mut HashMap<NodeIndex, usize>,
+    visiting: &mut HashSet<NodeIndex>,
+) -> usize {
+    let pkg = &graph.graph[idx];
+    if graph.is_project(pkg) || !needs_work(pkg) {
+        return 0;
+    }
+    if let Some(length) = lengths.get(&idx) {
+        return *length;
+    }
+    // dev-dependencies can form cycles
+    if !visiting.insert(idx) {
+        return 0;
+    }
+
+    let longest = graph
+        .graph
+        .neighbors(idx)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|dep| chain(graph, dep, lengths, visiting))
+        .max()
+        .unwrap_or(0);
+
+    visiting.remove(&idx);
+    lengths.insert(idx, longest + 1);
+    longest + 1
+}
+
+pub fn stats(graph: &Graph) -> Stats {
+    let counts = STATUSES
+        .iter()
+        .map(|status| {
+            let count = graph
+                .dependencies()
+                .filter(|pkg| pkg.packaging_status() == *status)
+                .count();
+            (*status, count)
+        })
+        .collect();
+
+    let crates = graph
+        .dependencies()
+        .map(|pkg| &pkg.name)
+        .collect::<HashSet<_>>()
+        .len();
+
+    let mut lengths = HashMap::new();
+    let mut visiting = HashSet::new();
+    let longest_chain = graph
+        .graph
+        .node_indices()
+        .map(|idx| chain(graph, idx, &mut lengths, &mut visiting))
+        .max()
+        .unwrap_or(0);
+
+    Stats {
+        release: graph
+            .graph
+            .node_weights()
+            .next()
+            .map_or_else(|| String::from("rawhide"), |pkg| pkg.rpmrelease.clone()),
+        counts,
+        crates,
+        nodes: graph.dependencies().count(),
+        longest_chain,
+    }
+}
+
+/// Collect the statistics for every release given with `--rpm-release`,
+/// `graph` is already populated for the first one
+fn all_stats(args: &RpmArgs, graph: &Graph) -> Result<Vec<Stats>, Error> {
+    let mut stats = vec![stats(graph)];
+    for release in args.rpm_release.iter().skip(1) {
+        let mut graph = graph.clone();
+        fedora::populate(args, &mut graph, Some(release.clone()))?;
+        stats.push(self::stats(&graph));
+    }
+    Ok(stats)
+}
+
+pub fn print(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
+    let stats = all_stats(args, graph)?;
+
+    let mut rows = vec![];
+    for (i, (status, _)) in stats[0].counts.iter().enumerate() {
+        rows.push((
+            status.name(),
+            stats.iter().map(|s| s.counts[i].1).collect::<Vec<_>>(),
+        ));
+    }
+    rows.push(("unique crates", stats.iter().map(|s| s.crates).collect()));
+    rows.push(("total nodes", stats.iter().map(|s| s.nodes).collect()));
+    rows.push((
+        "unpackaged depth",
+        stats.iter().map(|s| s.longest_chain).collect(),
+    ));
+
+    let width = stats
+        .iter()
+        .map(|s| s.release.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+
+    print!("{:<18}", "Summary");
+    for s in &stats {
+        print!(" {:>width$}", s.release);
+    }
+    println!();
+    for (name, values) in rows {
+        print!("{name:<18}");
+        for value in values {
+            print!(" {value:>width$}");
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::RpmArgs;
+    use crate::fedora::RpmInfo;
+    use crate::graph;
+    use crate::summary::stats;
+    use std::path::Path;
+
+    #[test]
+    fn longest_unpackaged_chain() {
+        let lockfile = toml::from_str(
+            r#"
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+            dependencies = ["a", "d"]
+
+            [[package]]
+            name = "a"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            dependencies = ["b"]
+
+            [[package]]
+            name = "b"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            dependencies = ["c"]
+
+            [[package]]
+            name = "c"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "d"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        )
+        .unwrap();
+        let mut graph =
+            graph::from_lockfile(&RpmArgs::default(), Path::new("Cargo.lock"), lockfile).unwrap();
+        for pkg in graph.graph.node_weights_mut() {
+            pkg.rpminfo = Some(RpmInfo {
+                in_rawhide: pkg.name == "b",
+                outdated: false,
+                compatible: false,
+                exact_match: pkg.name == "b",
+                version: String::new(),
+                package: String::new(),
+                evr: String::new(),
+                arch: String::new(),
+                source_package: String::new(),
+                pending: false,
+                note: None,
+                local: false,
+                spec: None,
+            });
+        }
+
+        let stats = stats(&graph);
+        // the app itself isn't counted
+        assert_eq!(stats.crates, 4);
+        assert_eq!(stats.nodes, 4);
+        // b is packaged, so a is the longest chain
+        assert_eq!(stats.longest_chain, 1);
+    }
+}
@@ -1,15 +1,28 @@
-#![allow(clippy::too_many_arguments)]
-
 use crate::args::{Charset, RpmArgs};
-use crate::fedora::Pkg;
-use crate::format::Pattern;
-use crate::graph::Graph;
+use crate::fedora::{PackagingProgress, Pkg};
+use crate::format::{Pattern, Position};
+use crate::graph::{Graph, PkgSpec};
+use crate::platform;
 use anyhow::{anyhow, Context, Error};
 use cargo_metadata::{DependencyKind, PackageId};
-use petgraph::visit::EdgeRef;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{Dfs, EdgeRef};
 use petgraph::EdgeDirection;
-use semver::Version;
+use semver::{Version, VersionReq};
 use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+struct Settings<'a> {
+    format: &'a Pattern,
+    direction: EdgeDirection,
+    symbols: &'a Symbols,
+    prefix: Prefix,
+    all: bool,
+    depth: Option<usize>,
+    prune: &'a [PkgSpec],
+    /// Crates on a path to one selected with `--only`, `None` if everything is shown
+    keep: Option<HashSet<NodeIndex>>,
+}
 
 #[derive(Clone, Copy)]
 enum Prefix {
@@ -41,7 +54,65 @@ static ASCII_SYMBOLS: Symbols = Symbols {
 
 pub fn print(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
     let format = Pattern::new(&args.format)?;
+    let settings = settings(args, graph, &format);
+
+    if args.needs_bump {
+        print_needs_bump(graph, &format);
+    } else if args.flat {
+        print_flat(graph, &settings, &args.only, args.workspace);
+    } else if args.duplicates {
+        for (i, package) in find_duplicates(graph).iter().enumerate() {
+            if i != 0 {
+                println!();
+            }
+
+            let root = &graph.graph[graph.nodes[*package]];
+            print_tree(graph, root, &settings)?;
+        }
+    } else if args.workspace && args.package.is_none() {
+        let mut members = graph
+            .roots()
+            .into_iter()
+            .map(|idx| &graph.graph[idx])
+            .collect::<Vec<_>>();
+        members.sort_by_key(|pkg| &pkg.name);
+
+        for (i, member) in members.iter().enumerate() {
+            if i != 0 {
+                println!();
+            }
+
+            print_tree(graph, member, &settings)?;
+        }
+
+        if args.all_targets {
+            print_patches(graph);
+        }
+        print_blockers(graph, &format);
+        print_published_members(&members, &format);
+        print_unsatisfied_specs(graph);
+    } else {
+        let root = match &args.package {
+            Some(package) => find_package(package, graph)?,
+            None => graph.root.as_ref().ok_or_else(|| {
+                anyhow!("this command requires running against an actual package in this workspace, or --workspace")
+            })?,
+        };
+        let root = &graph.graph[graph.nodes[root]];
+
+        print_tree(graph, root, &settings)?;
+
+        if args.all_targets {
+            print_patches(graph);
+        }
+        print_blockers(graph, &format);
+        print_unsatisfied_specs(graph);
+    }
+
+    Ok(())
+}
 
+fn settings<'a>(args: &'a RpmArgs, graph: &Graph, format: &'a Pattern) -> Settings<'a> {
     let direction = if args.invert || args.duplicates {
         EdgeDirection::Incoming
     } else {
@@ -61,28 +132,119 @@ pub fn print(args: &RpmArgs, graph: &Graph) -> Result<(), Error> {
         Prefix::Indent
     };
 
-    if args.duplicates {
-        for (i, package) in find_duplicates(graph).iter().enumerate() {
-            if i != 0 {
-                println!();
-            }
+    Settings {
+        format,
+        direction,
+        symbols,
+        prefix,
+        all: args.all,
+        depth: args.depth,
+        prune: &args.prune,
+        keep: (!args.only.is_empty())
+            .then(|| find_relevant(graph, &args.only, direction, args.all)),
+    }
+}
 
-            let root = &graph.graph[graph.nodes[*package]];
-            print_tree(graph, root, &format, direction, symbols, prefix, args.all);
+/// Render the dependency tree of the root package, or of every workspace
+/// member, without any of the sections following it
+pub fn render(args: &RpmArgs, graph: &Graph) -> Result<String, Error> {
+    let format = Pattern::new(&args.format)?;
+    let settings = settings(args, graph, &format);
+
+    let roots = match &args.package {
+        Some(package) => vec![graph.nodes[find_package(package, graph)?]],
+        None => graph.roots(),
+    };
+    let mut roots = roots
+        .into_iter()
+        .map(|idx| &graph.graph[idx])
+        .collect::<Vec<_>>();
+    roots.sort_by_key(|pkg| &pkg.name);
+
+    let mut out = String::new();
+    for (i, root) in roots.iter().enumerate() {
+        if i != 0 {
+            out.push('\n');
         }
-    } else {
-        let root = match &args.package {
-            Some(package) => find_package(package, graph)?,
-            None => graph.root.as_ref().ok_or_else(|| {
-                anyhow!("this command requires running against an actual package in this workspace")
-            })?,
-        };
-        let root = &graph.graph[graph.nodes[root]];
+        write_tree(&mut out, graph, root, &settings)?;
+    }
+
+    Ok(out)
+}
 
-        print_tree(graph, root, &format, direction, symbols, prefix, args.all);
+fn print_blockers(graph: &Graph, format: &Pattern) {
+    let mut blockers = graph
+        .graph
+        .node_weights()
+        .filter(|pkg| pkg.blocker.is_some() && !pkg.patch_out)
+        .collect::<Vec<_>>();
+    if blockers.is_empty() {
+        return;
     }
+    blockers.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
 
-    Ok(())
+    println!();
+    println!("Packaging blockers:");
+    for pkg in blockers {
+        println!(" {} {}", pkg.packaging_status(), format.display(pkg));
+    }
+}
+
+fn print_published_members(members: &[&Pkg], format: &Pattern) {
+    let published = members.iter().filter(|pkg| pkg.publish).collect::<Vec<_>>();
+    if published.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("Workspace members published on crates.io:");
+    for member in published {
+        println!(" {} {}", member.packaging_status(), format.display(member));
+    }
+}
+
+fn print_patches(graph: &Graph) {
+    let patches = platform::patches(graph);
+    if patches.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("Manifest sections to patch out for Fedora:");
+    for patch in patches {
+        println!(
+            " {} {} v{}: remove `{}` from [{}]",
+            PackagingProgress::PatchOut,
+            patch.package.name,
+            patch.package.version,
+            patch.dependency.name,
+            patch.section
+        );
+    }
+}
+
+fn print_unsatisfied_specs(graph: &Graph) {
+    if graph.unsatisfied_specs.is_empty() {
+        return;
+    }
+    let release = graph
+        .graph
+        .node_weights()
+        .next()
+        .map_or("rawhide", |pkg| pkg.rpmrelease.as_str());
+
+    println!();
+    println!("Pending specs with BuildRequires missing in {release}:");
+    for unsatisfied in &graph.unsatisfied_specs {
+        println!(
+            " {} {}",
+            PackagingProgress::SpecPending,
+            unsatisfied.file_name()
+        );
+        for requirement in &unsatisfied.requirements {
+            println!("     {requirement}");
+        }
+    }
 }
 
 fn find_package<'a>(package: &str, graph: &'a Graph) -> Result<&'a PackageId, Error> {
@@ -101,10 +263,10 @@ fn find_package<'a>(package: &str, graph: &'a Graph) -> Result<&'a PackageId, Er
             continue;
         }
 
-        if let Some(version) = &version {
-            if package.version != *version {
-                continue;
-            }
+        if let Some(version) = &version
+            && package.version != *version
+        {
+            continue;
         }
 
         candidates.push(package);
@@ -150,60 +312,195 @@ fn find_duplicates(graph: &Graph) -> Vec<&PackageId> {
     duplicates
 }
 
-fn print_tree<'a>(
-    graph: &'a Graph,
-    root: &'a Pkg,
-    format: &Pattern,
+/// Collect the crates with one of the given statuses and every crate on a path leading to them
+fn find_relevant(
+    graph: &Graph,
+    only: &[PackagingProgress],
     direction: EdgeDirection,
-    symbols: &Symbols,
-    prefix: Prefix,
     all: bool,
-) {
+) -> HashSet<NodeIndex> {
+    let mut stack = graph
+        .graph
+        .node_indices()
+        .filter(|idx| only.contains(&graph.graph[*idx].packaging_status()))
+        .collect::<Vec<_>>();
+
+    let mut relevant = HashSet::new();
+    while let Some(idx) = stack.pop() {
+        if !relevant.insert(idx) {
+            continue;
+        }
+
+        for edge in graph.graph.edges_directed(idx, direction.opposite()) {
+            let parent = match direction {
+                EdgeDirection::Outgoing => edge.source(),
+                EdgeDirection::Incoming => edge.target(),
+            };
+            // the path is cut short where the tree stops expanding packaged crates
+            if all || graph.graph[parent].show_dependencies() {
+                stack.push(parent);
+            }
+        }
+    }
+
+    relevant
+}
+
+/// The workspace members each crate is a (transitive) dependency of
+fn affected_members(graph: &Graph) -> HashMap<NodeIndex, Vec<&str>> {
+    let mut affected: HashMap<NodeIndex, Vec<&str>> = HashMap::new();
+
+    let mut members = graph.roots();
+    members.sort_by_key(|idx| &graph.graph[*idx].name);
+    for member in members {
+        let name = graph.graph[member].name.as_str();
+        let mut dfs = Dfs::new(&graph.graph, member);
+        while let Some(idx) = dfs.next(&graph.graph) {
+            if idx != member {
+                affected.entry(idx).or_default().push(name);
+            }
+        }
+    }
+
+    affected
+}
+
+fn print_flat(graph: &Graph, settings: &Settings, only: &[PackagingProgress], workspace: bool) {
+    let affected = if workspace {
+        affected_members(graph)
+    } else {
+        HashMap::new()
+    };
+
+    // as in the tree, a pruned crate hides the dependencies only it pulls in
+    let mut reachable = HashSet::new();
+    let mut stack = graph.roots();
+    while let Some(idx) = stack.pop() {
+        if !reachable.insert(idx) {
+            continue;
+        }
+        for dep in graph.graph.neighbors_directed(idx, EdgeDirection::Outgoing) {
+            if !settings
+                .prune
+                .iter()
+                .any(|spec| spec.matches(&graph.graph[dep]))
+            {
+                stack.push(dep);
+            }
+        }
+    }
+
+    let mut packages = reachable
+        .iter()
+        .copied()
+        .filter(|idx| only.is_empty() || only.contains(&graph.graph[*idx].packaging_status()))
+        .collect::<Vec<_>>();
+    packages.sort_by(|a, b| {
+        let (a, b) = (&graph.graph[*a], &graph.graph[*b]);
+        (&a.name, &a.version).cmp(&(&b.name, &b.version))
+    });
+
+    for idx in packages {
+        let package = &graph.graph[idx];
+
+        let mut dependents = graph
+            .graph
+            .edges_directed(idx, EdgeDirection::Incoming)
+            .filter(|edge| reachable.contains(&edge.source()))
+            .map(|edge| &graph.graph[edge.source()])
+            .map(|dependent| format!("{} v{}", dependent.name, dependent.version))
+            .collect::<Vec<_>>();
+        dependents.sort();
+        dependents.dedup();
+
+        let status = package.packaging_status();
+        let mut line = format!(
+            " {} {:<10} {}",
+            status,
+            status.name(),
+            settings.format.display(package)
+        );
+        if !dependents.is_empty() {
+            line.push_str(&format!(" (required by {})", dependents.join(", ")));
+        }
+        if let Some(members) = affected.get(&idx) {
+            line.push_str(&format!(" [affects {}]", members.join(", ")));
+        }
+        println!("{line}");
+    }
+}
+
+fn print_needs_bump(graph: &Graph, format: &Pattern) {
+    let mut packages = graph
+        .graph
+        .node_weights()
+        .filter(|pkg| pkg.freshness().is_some_and(|f| f.needs_bump()))
+        .collect::<Vec<_>>();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    for package in packages {
+        println!("{}", format.display(package));
+    }
+}
+
+fn print_tree<'a>(graph: &'a Graph, root: &'a Pkg, settings: &Settings) -> Result<(), Error> {
+    let mut out = String::new();
+    write_tree(&mut out, graph, root, settings)?;
+    print!("{out}");
+    Ok(())
+}
+
+fn write_tree<'a>(
+    out: &mut String,
+    graph: &'a Graph,
+    root: &'a Pkg,
+    settings: &Settings,
+) -> fmt::Result {
     let mut visited_deps = HashSet::new();
     let mut levels_continue = vec![];
 
-    print_package(
+    write_package(
+        out,
         graph,
         root,
-        format,
-        direction,
-        symbols,
-        prefix,
-        all,
+        settings,
         &mut visited_deps,
         &mut levels_continue,
-    );
+        None,
+    )
 }
 
-fn print_package<'a>(
+fn write_package<'a>(
+    out: &mut String,
     graph: &'a Graph,
     package: &'a Pkg,
-    format: &Pattern,
-    direction: EdgeDirection,
-    symbols: &Symbols,
-    prefix: Prefix,
-    all: bool,
+    settings: &Settings,
     visited_deps: &mut HashSet<&'a PackageId>,
     levels_continue: &mut Vec<bool>,
-) {
+    parent: Option<(&'a Pkg, DependencyKind)>,
+) -> fmt::Result {
     let treeline = {
         let mut line = "".to_string();
         line.push_str(&format!(" {} ", &package.packaging_status()));
-        match prefix {
+        match settings.prefix {
             Prefix::Depth => line.push_str(&format!("{}", levels_continue.len())),
             Prefix::Indent => {
                 if let Some((last_continues, rest)) = levels_continue.split_last() {
                     for continues in rest {
-                        let c = if *continues { symbols.down } else { " " };
+                        let c = if *continues {
+                            settings.symbols.down
+                        } else {
+                            " "
+                        };
                         line.push_str(&format!("{c}   "));
                     }
 
                     let c = if *last_continues {
-                        symbols.tee
+                        settings.symbols.tee
                     } else {
-                        symbols.ell
+                        settings.symbols.ell
                     };
-                    line.push_str(&format!("{0}{1}{1} ", c, symbols.right));
+                    line.push_str(&format!("{0}{1}{1} ", c, settings.symbols.right));
                 }
             }
             Prefix::None => {}
@@ -211,11 +508,33 @@ fn print_package<'a>(
         line
     };
 
-    let pkg_status_s = format.display(package).to_string();
-    println!("{}{}", treeline, pkg_status_s);
+    let expand = settings.all || package.show_dependencies();
+    let new = settings.all || visited_deps.insert(&package.id);
+    let star = if !new && expand && has_dependencies(graph, package, settings) {
+        " (*)"
+    } else {
+        ""
+    };
 
-    if !all && !package.show_dependencies() {
-        return;
+    let position = Position {
+        requirement: parent.and_then(|(parent, kind)| match settings.direction {
+            EdgeDirection::Outgoing => requirement(parent, package, kind),
+            EdgeDirection::Incoming => requirement(package, parent, kind),
+        }),
+        kind: parent.map(|(_, kind)| kind),
+        depth: levels_continue.len(),
+    };
+    let pkg_status_s = settings.format.display_at(package, position).to_string();
+    writeln!(out, "{}{}{}", treeline, pkg_status_s, star)?;
+
+    if !new || !expand {
+        return Ok(());
+    }
+
+    if let Some(depth) = settings.depth
+        && levels_continue.len() >= depth
+    {
+        return Ok(());
     }
 
     for kind in &[
@@ -223,49 +542,82 @@ fn print_package<'a>(
         DependencyKind::Build,
         DependencyKind::Development,
     ] {
-        print_dependencies(
+        write_dependencies(
+            out,
             graph,
             package,
-            format,
-            direction,
-            symbols,
-            prefix,
-            all,
+            settings,
             visited_deps,
             levels_continue,
             *kind,
-        );
+        )?;
     }
+
+    Ok(())
 }
 
-fn print_dependencies<'a>(
+fn dependencies<'a>(
     graph: &'a Graph,
     package: &'a Pkg,
-    format: &Pattern,
-    direction: EdgeDirection,
-    symbols: &Symbols,
-    prefix: Prefix,
-    all: bool,
-    visited_deps: &mut HashSet<&'a PackageId>,
-    levels_continue: &mut Vec<bool>,
-    kind: DependencyKind,
-) {
+    settings: &Settings,
+    kind: Option<DependencyKind>,
+) -> Vec<&'a Pkg> {
     let idx = graph.nodes[&package.id];
     let mut deps = vec![];
-    for edge in graph.graph.edges_directed(idx, direction) {
-        if *edge.weight() != kind {
+    for edge in graph.graph.edges_directed(idx, settings.direction) {
+        if kind.is_some_and(|kind| edge.weight().kind != kind) {
             continue;
         }
 
-        let dep = match direction {
+        let dep = match settings.direction {
             EdgeDirection::Incoming => &graph.graph[edge.source()],
             EdgeDirection::Outgoing => &graph.graph[edge.target()],
         };
+        if settings.prune.iter().any(|spec| spec.matches(dep)) {
+            continue;
+        }
+        if let Some(keep) = &settings.keep
+            && !keep.contains(&graph.nodes[&dep.id])
+        {
+            continue;
+        }
         deps.push(dep);
     }
+    deps
+}
+
+/// The version requirement `dependent` declares for `dependency`
+fn requirement<'a>(
+    dependent: &'a Pkg,
+    dependency: &Pkg,
+    kind: DependencyKind,
+) -> Option<&'a VersionReq> {
+    dependent
+        .dependencies
+        .iter()
+        .find(|dep| {
+            dep.name == dependency.name && dep.kind == kind && dep.req.matches(&dependency.version)
+        })
+        .map(|dep| &dep.req)
+}
+
+fn has_dependencies(graph: &Graph, package: &Pkg, settings: &Settings) -> bool {
+    !dependencies(graph, package, settings, None).is_empty()
+}
+
+fn write_dependencies<'a>(
+    out: &mut String,
+    graph: &'a Graph,
+    package: &'a Pkg,
+    settings: &Settings,
+    visited_deps: &mut HashSet<&'a PackageId>,
+    levels_continue: &mut Vec<bool>,
+    kind: DependencyKind,
+) -> fmt::Result {
+    let mut deps = dependencies(graph, package, settings, Some(kind));
 
     if deps.is_empty() {
-        return;
+        return Ok(());
     }
 
     // ensure a consistent output ordering
@@ -278,31 +630,35 @@ fn print_dependencies<'a>(
         _ => unreachable!(),
     };
 
-    if let Prefix::Indent = prefix {
-        if let Some(name) = name {
-            for continues in &**levels_continue {
-                let c = if *continues { symbols.down } else { " " };
-                print!("{c}   ");
-            }
-
-            println!("{name}");
+    if let Prefix::Indent = settings.prefix
+        && let Some(name) = name
+    {
+        for continues in &**levels_continue {
+            let c = if *continues {
+                settings.symbols.down
+            } else {
+                " "
+            };
+            write!(out, "{c}   ")?;
         }
+
+        writeln!(out, "{name}")?;
     }
 
     let mut it = deps.iter().peekable();
     while let Some(dependency) = it.next() {
         levels_continue.push(it.peek().is_some());
-        print_package(
+        write_package(
+            out,
             graph,
             dependency,
-            format,
-            direction,
-            symbols,
-            prefix,
-            all,
+            settings,
             visited_deps,
             levels_continue,
-        );
+            Some((package, kind)),
+        )?;
         levels_continue.pop();
     }
+
+    Ok(())
 }
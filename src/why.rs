@@ -0,0 +1,174 @@
+use crate::args::{Charset, RpmArgs, WhyArgs};
+use crate::fedora::Pkg;
+use crate::format::Pattern;
+use crate::graph::{Edge, Graph};
+use anyhow::{anyhow, Error};
+use cargo_metadata::DependencyKind;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeDirection;
+use std::collections::HashSet;
+
+/// Stop enumerating paths once this many have been found
+const MAX_PATHS: usize = 100;
+
+pub fn print(args: &RpmArgs, why: &WhyArgs, graph: &Graph) -> Result<(), Error> {
+    let format = Pattern::new(&args.format)?;
+
+    let targets = graph
+        .graph
+        .node_indices()
+        .filter(|idx| why.package.matches(&graph.graph[*idx]))
+        .collect::<HashSet<_>>();
+    if targets.is_empty() {
+        return Err(anyhow!("no crates found for package `{}`", why.package));
+    }
+
+    let reaching = reaching(graph, &targets);
+    let mut paths = vec![];
+    for root in graph.roots() {
+        if reaching.contains(&root) {
+            find_paths(graph, root, &targets, &reaching, &mut vec![], &mut paths);
+        }
+    }
+    // one path more than shown is searched to tell whether any were left out
+    let truncated = paths.len() > MAX_PATHS;
+    paths.truncate(MAX_PATHS);
+
+    let arrow = match args.charset {
+        Charset::Utf8 => "└─",
+        Charset::Ascii => "`-",
+    };
+
+    for (i, path) in paths.iter().enumerate() {
+        if i != 0 {
+            println!();
+        }
+
+        let root = &graph.graph[path[0]];
+        println!(" {} {}", root.packaging_status(), format.display(root));
+
+        for (depth, hop) in path.windows(2).enumerate() {
+            let parent = &graph.graph[hop[0]];
+            let child = &graph.graph[hop[1]];
+            let edges = graph
+                .graph
+                .edges_connecting(hop[0], hop[1])
+                .map(|edge| edge.weight())
+                .collect::<Vec<_>>();
+
+            println!(
+                " {} {}{} {} [{}]",
+                child.packaging_status(),
+                "   ".repeat(depth),
+                arrow,
+                format.display(child),
+                describe_hop(parent, child, &edges),
+            );
+        }
+    }
+
+    if truncated {
+        println!();
+        println!("Stopped after {MAX_PATHS} paths");
+    }
+
+    Ok(())
+}
+
+/// Every node from which one of the targets can be reached
+fn reaching(graph: &Graph, targets: &HashSet<NodeIndex>) -> HashSet<NodeIndex> {
+    let mut stack = targets.iter().copied().collect::<Vec<_>>();
+    let mut reaching = HashSet::new();
+    while let Some(idx) = stack.pop() {
+        if reaching.insert(idx) {
+            stack.extend(graph.graph.neighbors_directed(idx, EdgeDirection::Incoming));
+        }
+    }
+    reaching
+}
+
+fn find_paths(
+    graph: &Graph,
+    idx: NodeIndex,
+    targets: &HashSet<NodeIndex>,
+    reaching: &HashSet<NodeIndex>,
+    path: &mut Vec<NodeIndex>,
+    paths: &mut Vec<Vec<NodeIndex>>,
+) {
+    if paths.len() > MAX_PATHS {
+        return;
+    }
+
+    path.push(idx);
+    if targets.contains(&idx) {
+        paths.push(path.clone());
+    } else {
+        let mut children = graph
+            .graph
+            .edges(idx)
+            .map(|edge| edge.target())
+            .filter(|child| reaching.contains(child) && !path.contains(child))
+            .collect::<Vec<_>>();
+        // ensure a consistent output ordering
+        children.sort_by_key(|child| &graph.graph[*child].id);
+        children.dedup();
+
+        for child in children {
+            find_paths(graph, child, targets, reaching, path, paths);
+        }
+    }
+    path.pop();
+}
+
+fn describe_hop(parent: &Pkg, child: &Pkg, edges: &[&Edge]) -> String {
+    let mut parts = edges
+        .iter()
+        .map(|edge| match edge.kind {
+            DependencyKind::Build => "build",
+            DependencyKind::Development => "dev",
+            _ => "normal",
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let declared = parent
+        .dependencies
+        .iter()
+        .filter(|dep| dep.name == child.name && dep.req.matches(&child.version))
+        .collect::<Vec<_>>();
+
+    if let Some(dep) = declared.iter().find(|dep| dep.optional) {
+        let name = dep.rename.as_ref().unwrap_or(&dep.name);
+        let enabled_by = parent
+            .features
+            .iter()
+            .filter(|feature| {
+                *feature == name
+                    || parent.feature_map.get(*feature).is_some_and(|enables| {
+                        enables.iter().any(|e| {
+                            e == name
+                                || e.strip_prefix("dep:") == Some(name)
+                                || e.strip_prefix(name).is_some_and(|f| f.starts_with('/'))
+                        })
+                    })
+            })
+            .map(|feature| format!("`{feature}`"))
+            .collect::<Vec<_>>();
+
+        if enabled_by.is_empty() {
+            parts.push_str(", optional");
+        } else {
+            parts.push_str(&format!(", optional via {}", enabled_by.join(", ")));
+        }
+    }
+
+    // the features the crate ends up with after unifying every dependent's requests
+    let mut features = child.features.clone();
+    features.sort();
+    if !features.is_empty() {
+        parts.push_str(&format!("; features: {}", features.join(", ")));
+    }
+
+    parts
+}